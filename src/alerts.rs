@@ -0,0 +1,726 @@
+//! Price alerts
+//!
+//! Rules are loaded from `alerts.toml` in the config directory and can be created and edited in the
+//! alerts screen, every change gets written back to the file.
+//!
+//! ```toml
+//! [[alert]]
+//! product = "BTC-USD"
+//! kind = "above"
+//! price = 70000
+//!
+//! [[alert]]
+//! product = "SOL-USDC"
+//! kind = "percent_move"
+//! percent = 2.5
+//! window_secs = 300
+//! repeat = true
+//! ```
+//!
+//! In the TUI the same rules are written in a short form, see [`AlertRule::parse`]. Where fired
+//! alerts get sent besides the history is configured in the same file, see [`crate::delivery`].
+
+use std::{
+    collections::VecDeque,
+    fmt::Display,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail};
+use chrono::{DateTime, Utc};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    widgets::{Cell, List, ListItem, ListState, Row, Table, TableState},
+};
+use ringbuffer::RingBuffer;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    delivery::Delivery,
+    gradient_widget::GradientConfig,
+    keymap::{Action, Keymap, Mode},
+    sockets::ws_messages,
+    theme::Theme,
+    utils::{config_dir, convert_timestamp_to_locale},
+};
+
+/// How many fired alerts are kept in the history
+const MAX_HISTORY: usize = 500;
+/// How long a snooze lasts
+pub const SNOOZE_SECS: i64 = 300;
+/// How long the border of a chart flashes after one of its alerts fired
+pub const FLASH_MS: i64 = 5000;
+/// How many windows before the current one are used as the baseline for volume spikes
+const VOLUME_BASELINE_WINDOWS: f64 = 5.0;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    /// Price crosses above the value
+    Above { price: f64 },
+    /// Price crosses below the value
+    Below { price: f64 },
+    /// Price moved by at least this many percent, in either direction, within the window
+    PercentMove { percent: f64, window_secs: u64 },
+    /// The spread between best ask and best bid is wider than this many percent of the price
+    SpreadAbove { percent: f64 },
+    /// The traded volume in the window is `factor` times the average of the windows before it
+    VolumeSpike { factor: f64, window_secs: u64 },
+}
+
+impl Display for Condition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Condition::Above { price } => write!(f, "> {price}"),
+            Condition::Below { price } => write!(f, "< {price}"),
+            Condition::PercentMove {
+                percent,
+                window_secs,
+            } => write!(f, "move {percent}% {window_secs}s"),
+            Condition::SpreadAbove { percent } => write!(f, "spread {percent}%"),
+            Condition::VolumeSpike {
+                factor,
+                window_secs,
+            } => write!(f, "volume {factor}x {window_secs}s"),
+        }
+    }
+}
+
+impl Condition {
+    /// Checks the condition against the buffered messages of the product. Returns if the condition
+    /// is met together with the value that got compared, `None` if there is no data yet.
+    fn check(&self, product: &str, now: f64) -> Option<(bool, f64)> {
+        let msgs = ws_messages.lock();
+        let buf = msgs.get(product)?;
+        let last = buf.back()?;
+        let price = last.price.parse::<f64>().ok()?;
+
+        let points = |since: f64| {
+            buf.iter().filter_map(move |m| {
+                let t = m.time.parse::<DateTime<Utc>>().ok()?.timestamp_millis() as f64;
+                (t >= since).then_some((t, m))
+            })
+        };
+
+        match self {
+            Condition::Above { price: v } => Some((price >= *v, price)),
+            Condition::Below { price: v } => Some((price <= *v, price)),
+            Condition::PercentMove {
+                percent,
+                window_secs,
+            } => {
+                let (_, first) = points(now - *window_secs as f64 * 1000.0).next()?;
+                let start = first.price.parse::<f64>().ok().filter(|p| *p != 0.0)?;
+                let change = (price / start - 1.0) * 100.0;
+                Some((change.abs() >= percent.abs(), change))
+            }
+            Condition::SpreadAbove { percent } => {
+                let bid = last.best_bid.parse::<f64>().ok()?;
+                let ask = last.best_ask.parse::<f64>().ok()?;
+                let spread = (ask - bid) / price * 100.0;
+                Some((spread >= *percent, spread))
+            }
+            Condition::VolumeSpike {
+                factor,
+                window_secs,
+            } => {
+                let window = *window_secs as f64 * 1000.0;
+                let (mut current, mut baseline) = (0.0, 0.0);
+                for (t, m) in points(now - window * (VOLUME_BASELINE_WINDOWS + 1.0)) {
+                    let size = m.last_size.parse::<f64>().unwrap_or(0.0);
+                    if t >= now - window {
+                        current += size;
+                    } else {
+                        baseline += size;
+                    }
+                }
+
+                let avg = baseline / VOLUME_BASELINE_WINDOWS;
+                if avg <= 0.0 {
+                    return Some((false, 0.0));
+                }
+                Some((current >= avg * factor, current / avg))
+            }
+        }
+    }
+}
+
+/// Runtime state of a rule, this doesnt get saved
+#[derive(Debug, Clone, Default)]
+struct RuleState {
+    /// Was the condition met at the last check, `None` before the first one. Alerts only fire when
+    /// it switches to true, so a condition that already holds on startup doesnt fire.
+    active: Option<bool>,
+    /// Unix ms until which the rule doesnt fire
+    snoozed_until: Option<i64>,
+}
+
+fn default_repeat() -> bool {
+    false
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub product: String,
+    #[serde(flatten)]
+    pub condition: Condition,
+    /// Repeating rules fire every time the condition becomes true, one-shot rules only once
+    #[serde(default = "default_repeat")]
+    pub repeat: bool,
+    /// One-shot rules are done after they fired once, this is saved so they stay done
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    done: bool,
+    #[serde(skip)]
+    state: RuleState,
+}
+
+impl Display for AlertRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.product, self.condition)?;
+        if self.repeat {
+            write!(f, " repeat")?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses `2%`, `300s` or `3x` into the number
+fn parse_unit(v: &str, unit: char) -> anyhow::Result<f64> {
+    v.trim_end_matches(unit)
+        .parse::<f64>()
+        .with_context(|| format!("Expected a number like 5{unit} but got '{v}'"))
+}
+
+/// Parses a window like `300s`, it has to be a whole number of seconds greater than 0
+fn parse_secs(v: &str) -> anyhow::Result<u64> {
+    v.trim_end_matches('s')
+        .parse::<u64>()
+        .ok()
+        .filter(|s| *s > 0)
+        .with_context(|| format!("Expected a window like 300s but got '{v}'"))
+}
+
+impl AlertRule {
+    pub fn new(product: String, condition: Condition, repeat: bool) -> Self {
+        Self {
+            product,
+            condition,
+            repeat,
+            done: false,
+            state: RuleState::default(),
+        }
+    }
+
+    /// Parses the short form of a rule:
+    ///
+    /// - `BTC-USD > 70000` / `BTC-USD < 60000`
+    /// - `BTC-USD move 2% 300s`
+    /// - `BTC-USD spread 0.1%`
+    /// - `BTC-USD volume 3x 60s`
+    ///
+    /// Appending `repeat` makes the rule fire every time instead of only once.
+    pub fn parse(v: &str) -> anyhow::Result<Self> {
+        let mut parts = v.split_whitespace().collect::<Vec<&str>>();
+        let repeat = parts.last() == Some(&"repeat");
+        if repeat {
+            parts.pop();
+        }
+
+        let [product, rest @ ..] = parts.as_slice() else {
+            bail!("Expected a product like BTC-USD");
+        };
+        if !product.contains('-') {
+            bail!("Expected a product like BTC-USD but got '{product}'");
+        }
+
+        let condition = match rest {
+            [">", price] => Condition::Above {
+                price: price.parse().context("Invalid price")?,
+            },
+            ["<", price] => Condition::Below {
+                price: price.parse().context("Invalid price")?,
+            },
+            ["move", percent, window] => Condition::PercentMove {
+                percent: parse_unit(percent, '%')?,
+                window_secs: parse_secs(window)?,
+            },
+            ["spread", percent] => Condition::SpreadAbove {
+                percent: parse_unit(percent, '%')?,
+            },
+            ["volume", factor, window] => Condition::VolumeSpike {
+                factor: parse_unit(factor, 'x')?,
+                window_secs: parse_secs(window)?,
+            },
+            _ => bail!("Unknown condition, expected one of: > <, move, spread, volume"),
+        };
+
+        Ok(Self::new(product.to_uppercase(), condition, repeat))
+    }
+
+    pub fn is_snoozed(&self, now: i64) -> bool {
+        self.state.snoozed_until.is_some_and(|t| t > now)
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Checks the rule and returns the fired alert if it triggered
+    fn evaluate(&mut self, now: i64) -> Option<FiredAlert> {
+        let (hit, value) = self.condition.check(&self.product, now as f64)?;
+        let was_active = self.state.active.replace(hit);
+        let rising = hit && was_active == Some(false);
+
+        if !rising || self.done || self.is_snoozed(now) {
+            return None;
+        }
+        if !self.repeat {
+            self.done = true;
+        }
+
+        Some(FiredAlert {
+            time: now,
+            product: self.product.clone(),
+            rule: self.to_string(),
+            value,
+            done: self.done,
+        })
+    }
+}
+
+/// An alert that went off
+#[derive(Debug, Clone)]
+pub struct FiredAlert {
+    /// Unix ms
+    pub time: i64,
+    pub product: String,
+    /// The rule in its short form
+    pub rule: String,
+    /// The value that triggered the rule, e.g. the price or the percent change
+    pub value: f64,
+    /// The rule was one-shot and is done now, so the rules need to be saved
+    pub done: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AlertFile {
+    #[serde(default, rename = "alert")]
+    alerts: Vec<AlertRule>,
+    #[serde(default)]
+    delivery: Delivery,
+}
+
+/// All rules together with the history of fired alerts
+#[derive(Debug, Clone, Default)]
+pub struct AlertEngine {
+    pub rules: Vec<AlertRule>,
+    pub history: VecDeque<FiredAlert>,
+    /// Where fired alerts get sent to
+    pub delivery: Delivery,
+    /// Selected rule in the alerts screen
+    pub selected: usize,
+    path: PathBuf,
+}
+
+impl AlertEngine {
+    pub fn default_path() -> PathBuf {
+        config_dir().join("alerts.toml")
+    }
+
+    /// Loads the rules, a missing file just means there are no rules yet
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let mut engine = Self {
+            path: path.to_path_buf(),
+            ..Default::default()
+        };
+        if !path.exists() {
+            return Ok(engine);
+        }
+
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let file: AlertFile = toml::from_str(&raw)
+            .with_context(|| format!("Invalid alerts in {}", path.display()))?;
+        file.delivery
+            .validate()
+            .with_context(|| format!("Invalid alerts in {}", path.display()))?;
+        engine.rules = file.alerts;
+        engine.delivery = file.delivery;
+        Ok(engine)
+    }
+
+    /// Writes the rules back to the file they got loaded from
+    pub fn save(&self) -> anyhow::Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let file = AlertFile {
+            alerts: self.rules.clone(),
+            delivery: self.delivery.clone(),
+        };
+        std::fs::write(&self.path, toml::to_string_pretty(&file)?)
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    /// The products the rules are watching, they need to be subscribed even without a chart
+    pub fn products(&self) -> Vec<String> {
+        let mut products = self
+            .rules
+            .iter()
+            .map(|r| r.product.clone())
+            .collect::<Vec<String>>();
+        products.sort();
+        products.dedup();
+        products
+    }
+
+    /// Evaluates every rule and returns the ones that fired, they also get added to the history.
+    /// Only finished one-shot rules change what gets saved, see [`FiredAlert::done`].
+    pub fn check(&mut self) -> Vec<FiredAlert> {
+        let now = chrono::Local::now().timestamp_millis();
+        let fired = self
+            .rules
+            .iter_mut()
+            .filter_map(|r| r.evaluate(now))
+            .collect::<Vec<FiredAlert>>();
+
+        for f in fired.iter() {
+            self.delivery.deliver(f);
+            self.history.push_front(f.clone());
+        }
+        self.history.truncate(MAX_HISTORY);
+        fired
+    }
+
+    /// Moves the selection by `by` rules, wrapping around at both ends
+    pub fn select(&mut self, by: isize) {
+        if self.rules.is_empty() {
+            self.selected = 0;
+            return;
+        }
+        self.selected =
+            (self.selected as isize + by).rem_euclid(self.rules.len() as isize) as usize;
+    }
+
+    /// Adds a new rule, or replaces the one at `idx`
+    pub fn upsert(&mut self, idx: Option<usize>, rule: AlertRule) -> anyhow::Result<()> {
+        match idx.and_then(|i| self.rules.get_mut(i)) {
+            Some(r) => *r = rule,
+            None => {
+                self.rules.push(rule);
+                self.selected = self.rules.len() - 1;
+            }
+        }
+        self.save()
+    }
+
+    pub fn delete_selected(&mut self) -> anyhow::Result<()> {
+        if self.selected < self.rules.len() {
+            self.rules.remove(self.selected);
+            self.select(0);
+        }
+        self.save()
+    }
+
+    /// Snoozes the selected rule, or wakes it up if its already snoozed
+    pub fn snooze_selected(&mut self) {
+        let now = chrono::Local::now().timestamp_millis();
+        if let Some(r) = self.rules.get_mut(self.selected) {
+            r.state.snoozed_until = if r.is_snoozed(now) {
+                None
+            } else {
+                Some(now + SNOOZE_SECS * 1000)
+            };
+        }
+    }
+
+    /// Switches the selected rule between one-shot and repeating, this also rearms it
+    pub fn toggle_repeat_selected(&mut self) -> anyhow::Result<()> {
+        if let Some(r) = self.rules.get_mut(self.selected) {
+            r.repeat = !r.repeat;
+            r.done = false;
+        }
+        self.save()
+    }
+}
+
+pub fn render_alerts(
+    frame: &mut Frame,
+    area: Rect,
+    engine: &AlertEngine,
+    error: Option<&str>,
+    keymap: &Keymap,
+    theme: &Theme,
+) {
+    let [rules_area, history_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Fill(1)]).areas(area);
+    let now = chrono::Local::now().timestamp_millis();
+
+    let rows = engine.rules.iter().map(|r| {
+        let status = if r.is_done() {
+            "done".to_string()
+        } else if r.is_snoozed(now) {
+            let left = (r.state.snoozed_until.unwrap_or(now) - now) / 1000;
+            format!("snoozed {left}s")
+        } else {
+            "armed".to_string()
+        };
+        let status_color = match status.as_str() {
+            "armed" => theme.info,
+            "done" => theme.muted,
+            _ => theme.warning,
+        };
+
+        Row::new(vec![
+            Cell::from(r.product.clone()),
+            Cell::from(r.condition.to_string()),
+            Cell::from(if r.repeat { "repeat" } else { "once" }),
+            Cell::from(status).fg(status_color),
+        ])
+        .style(Style::new().fg(theme.label))
+    });
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(14),
+            Constraint::Length(24),
+            Constraint::Length(8),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(
+        Row::new(["Product", "Condition", "Mode", "Status"])
+            .style(Style::new().bold().fg(theme.label)),
+    )
+    .row_highlight_style(Style::new().reversed())
+    .highlight_symbol("> ");
+
+    let title = match error {
+        Some(e) => format!("Alerts - {e}"),
+        None => format!(
+            "Alerts - {}",
+            keymap.hint(
+                Mode::Cursor,
+                &[
+                    (Action::NewAlert, "new"),
+                    (Action::EditAlert, "edit"),
+                    (Action::DeleteAlert, "delete"),
+                    (Action::SnoozeAlert, "snooze"),
+                    (Action::ToggleRepeat, "repeat"),
+                ],
+            )
+        ),
+    };
+    let mut state = TableState::default().with_selected(Some(engine.selected));
+    frame.render_stateful_widget(
+        theme
+            .panel(table)
+            .title(title)
+            .gradient_colors(GradientConfig::new_1(match error {
+                Some(_) => theme.error,
+                None => theme.border,
+            })),
+        rules_area,
+        &mut state,
+    );
+
+    let items = engine.history.iter().map(|f| {
+        ListItem::new(format!(
+            "{}  {:<14} {}  ({:.2})",
+            convert_timestamp_to_locale(f.time as f64),
+            f.product,
+            f.rule,
+            f.value
+        ))
+        .fg(theme.label)
+    });
+    frame.render_stateful_widget(
+        theme
+            .panel(List::new(items))
+            .title(match engine.delivery.errors().as_slice() {
+                [] => format!("History - {}", engine.history.len()),
+                errors => format!("History - {} - {}", engine.history.len(), errors.join(", ")),
+            }),
+        history_area,
+        &mut ListState::default(),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use ringbuffer::AllocRingBuffer;
+
+    use super::*;
+    use crate::sockets::WsMessage;
+
+    /// Puts a message `secs_ago` seconds before `now` into the buffer of the product. Every test
+    /// uses its own product, the buffer is shared.
+    fn push(product: &str, now: i64, secs_ago: i64, price: f64, size: f64) {
+        let time = DateTime::from_timestamp_millis(now - secs_ago * 1000).unwrap();
+        let msg = WsMessage {
+            product_id: product.into(),
+            price: price.to_string(),
+            best_bid: (price - 0.1).to_string(),
+            best_ask: (price + 0.1).to_string(),
+            last_size: size.to_string(),
+            time: time.to_rfc3339(),
+            ..Default::default()
+        };
+        ws_messages
+            .lock()
+            .entry(product.into())
+            .or_insert_with(|| AllocRingBuffer::new(100))
+            .enqueue(msg);
+    }
+
+    fn rule(product: &str, condition: Condition, repeat: bool) -> AlertRule {
+        AlertRule::new(product.into(), condition, repeat)
+    }
+
+    #[test]
+    fn parses_the_short_form() {
+        let r = AlertRule::parse("btc-usd move 2.5% 300s repeat").unwrap();
+        assert_eq!(r.product, "BTC-USD");
+        assert!(r.repeat);
+        assert_eq!(
+            r.condition,
+            Condition::PercentMove {
+                percent: 2.5,
+                window_secs: 300
+            }
+        );
+        assert_eq!(r.to_string(), "BTC-USD move 2.5% 300s repeat");
+    }
+
+    #[test]
+    fn rejects_broken_rules() {
+        for v in [
+            "",
+            "BTCUSD > 1",
+            "BTC-USD",
+            "BTC-USD > abc",
+            "BTC-USD >= 1",
+            "BTC-USD move 2%",
+            "BTC-USD move x% 300s",
+            "BTC-USD move 2% -300s",
+            "BTC-USD move 2% 1.5s",
+            "BTC-USD volume 3x 0s",
+            "BTC-USD spread",
+        ] {
+            assert!(AlertRule::parse(v).is_err(), "{v}");
+        }
+    }
+
+    #[test]
+    fn checks_every_condition() {
+        let now = chrono::Local::now().timestamp_millis();
+        for ago in [300, 250, 200, 150, 100] {
+            push("COND-USD", now, ago, 100.0, 1.0);
+        }
+        push("COND-USD", now, 10, 103.0, 4.0);
+
+        let check = |c: Condition| c.check("COND-USD", now as f64).unwrap();
+        assert_eq!(check(Condition::Above { price: 103.0 }), (true, 103.0));
+        assert_eq!(check(Condition::Above { price: 104.0 }), (false, 103.0));
+        assert_eq!(check(Condition::Below { price: 103.0 }), (true, 103.0));
+        assert_eq!(check(Condition::Below { price: 102.0 }), (false, 103.0));
+
+        let (hit, change) = check(Condition::PercentMove {
+            percent: 2.0,
+            window_secs: 400,
+        });
+        assert!(hit);
+        assert!((change - 3.0).abs() < 1e-9);
+        // only the newest message is inside the window
+        assert_eq!(
+            check(Condition::PercentMove {
+                percent: 2.0,
+                window_secs: 60
+            }),
+            (false, 0.0)
+        );
+
+        let (hit, spread) = check(Condition::SpreadAbove { percent: 0.1 });
+        assert!(hit);
+        assert!((spread - 0.2 / 103.0 * 100.0).abs() < 1e-9);
+        assert!(!check(Condition::SpreadAbove { percent: 0.5 }).0);
+
+        // 4 in the last minute against 5 spread over the 5 minutes before it
+        let volume = |factor| Condition::VolumeSpike {
+            factor,
+            window_secs: 60,
+        };
+        assert_eq!(check(volume(3.0)), (true, 4.0));
+        assert_eq!(check(volume(5.0)), (false, 4.0));
+
+        assert_eq!(
+            Condition::Above { price: 1.0 }.check("NONE-USD", now as f64),
+            None
+        );
+    }
+
+    #[test]
+    fn fires_only_when_crossing() {
+        let now = chrono::Local::now().timestamp_millis();
+        let mut r = rule("CROSS-USD", Condition::Above { price: 100.0 }, true);
+
+        // already above on the first check
+        push("CROSS-USD", now, 3, 110.0, 1.0);
+        assert!(r.evaluate(now).is_none());
+        push("CROSS-USD", now, 2, 120.0, 1.0);
+        assert!(r.evaluate(now).is_none());
+
+        push("CROSS-USD", now, 1, 90.0, 1.0);
+        assert!(r.evaluate(now).is_none());
+        push("CROSS-USD", now, 0, 105.0, 1.0);
+        let fired = r.evaluate(now).unwrap();
+        assert_eq!(fired.value, 105.0);
+        assert_eq!(fired.rule, "CROSS-USD > 100 repeat");
+    }
+
+    #[test]
+    fn snoozed_rules_dont_fire() {
+        let now = chrono::Local::now().timestamp_millis();
+        let mut r = rule("SNOOZE-USD", Condition::Above { price: 100.0 }, true);
+        push("SNOOZE-USD", now, 2, 90.0, 1.0);
+        r.evaluate(now);
+
+        r.state.snoozed_until = Some(now + 1000);
+        assert!(r.is_snoozed(now));
+        push("SNOOZE-USD", now, 1, 110.0, 1.0);
+        assert!(r.evaluate(now).is_none());
+
+        // the crossing during the snooze is gone, the next one fires
+        push("SNOOZE-USD", now, 0, 90.0, 1.0);
+        r.evaluate(now + 2000);
+        push("SNOOZE-USD", now, 0, 110.0, 1.0);
+        assert!(!r.is_snoozed(now + 2000));
+        assert!(r.evaluate(now + 2000).is_some());
+    }
+
+    #[test]
+    fn one_shot_rules_are_done_after_firing() {
+        let now = chrono::Local::now().timestamp_millis();
+        let mut once = rule("ONCE-USD", Condition::Below { price: 100.0 }, false);
+        let mut repeat = rule("ONCE-USD", Condition::Below { price: 100.0 }, true);
+        let mut step = |price| {
+            push("ONCE-USD", now, 0, price, 1.0);
+            (once.evaluate(now), repeat.evaluate(now))
+        };
+
+        step(110.0);
+        let (a, b) = step(90.0);
+        assert!(a.unwrap().done);
+        assert!(!b.unwrap().done);
+
+        step(110.0);
+        let (a, b) = step(90.0);
+        assert!(a.is_none());
+        assert!(b.is_some());
+        assert!(once.is_done());
+        assert!(!repeat.is_done());
+    }
+}
@@ -1,62 +1,121 @@
 use std::{
     collections::HashMap,
     fmt::Display,
+    io::Write,
     sync::Arc,
-    time::{SystemTime, UNIX_EPOCH},
-    usize,
+    time::{Instant, SystemTime, UNIX_EPOCH},
 };
 
 use crate::{
+    alerts::{AlertEngine, AlertRule, FLASH_MS, render_alerts},
+    arbitrage::{ArbitrageMonitor, render_arbitrage},
+    commands::{Command, CommandLine, export_csv},
+    comparison::render_comparison,
+    config::{ConfigWatch, Settings},
+    currency::{Currency, rate},
     events::{AppEvent, Event, EventHandler},
+    frame_stats::{FrameStats, render_frame_stats},
     gradient_widget::{GradientConfig, GradientWrapper},
-    memes::{MEMES, XorShift32},
-    sockets::{WsMessage, ws_messages},
-    utils::CURRENCIES,
+    heatmap::render_heatmap,
+    help::render_help,
+    indicators::{IndicatorKind, IndicatorLine, IndicatorName, PanelIndicators},
+    keymap::{Action, KeyBinding, KeySequence, Lookup, Mode},
+    ledger::{CostBasisMethod, Ledger, render_ledger},
+    memes::Banner,
+    oscillators::WithOscillator,
+    overview::{OverviewState, render_overview},
+    portfolio::{Portfolio, render_portfolio},
+    pressure::{Pressure, WithPressure, prints},
+    products::{Catalog, Picker},
+    prompt::{Prompt, PromptTarget},
+    sockets::{FeedCommand, WsMessage, message_count, ws_messages},
+    terminal::degrade,
+    toast::{Toast, render_toast},
+    utils::convert_timestamp_to_locale,
 };
 
-use chrono::{DateTime, Local, TimeZone, Utc};
+use anyhow::bail;
+use chrono::DateTime;
 use lazy_static::lazy_static;
 use ratatui::{
     DefaultTerminal, Frame,
     crossterm::event::{KeyCode, KeyEvent, KeyModifiers},
-    layout::{self, Constraint, Layout, Rect},
-    style::{Color, Stylize},
+    layout::{Constraint, Layout, Rect},
+    style::Stylize,
     symbols,
     text::{Line, Text},
-    widgets::{Axis, Chart, Dataset, Paragraph},
+    widgets::{Axis, Chart, Dataset, GraphType, Paragraph},
 };
 use ringbuffer::RingBuffer;
+use tokio::sync::mpsc;
 
-use crate::utils::CRYPTO_COLOR_CODES;
-
-pub const UPPER_COLOR_BOUND: u8 = 255;
-pub const LOWER_COLOR_BOUND: u8 = 150;
-/// This should be easily subtractable/ addable to the two values above be. Otherwise the type goes
-/// out of bounds and the program panics. So if the bounds are 150-255 you should use either 1 or 5
-/// etc.
-pub const CHANGE_COLOR_BY: u8 = 5;
+/// How long the border gradient takes to go around a chart once
+const ROTATION_MS: i64 = 8000;
+/// Frames per second for the turning borders when nothing else changes, so idle charts stay cheap
+const ROTATION_FPS: i64 = 4;
+/// How long the border of a chart lights up after its price moved
+const PULSE_MS: i64 = 700;
+/// How much of the up/ down color the border gets right when the price moved
+const PULSE_STRENGTH: f32 = 0.7;
 
 lazy_static! {
     static ref WATCHING_AMOUNT: Arc<i32> = Arc::new(0);
 }
 
-fn convert_timestamp_to_locale(ts: f64) -> String {
-    let local: DateTime<Local> = Utc
-        .timestamp_millis_opt(ts as i64)
-        .unwrap()
-        .with_timezone(&Local);
-    local.format("%Y-%m-%d %H:%M:%S").to_string()
-}
-
 const BODY_MIN_H: i32 = 10;
 const BODY_MIN_W: i32 = 46;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
 pub enum WindowType {
+    /// The focused coin gets the big panel on the left, the others are stacked in a column on the
+    /// right
     Master,
+    /// Every coin gets the same amount of space in a grid
+    #[default]
     Splace,
 }
 
-fn calc_body_layout(area: Rect, amount: usize, window_type: WindowType) -> Vec<Rect> {
+impl WindowType {
+    /// Returns the layout that comes after this one, used for cycling through them at runtime
+    pub fn cycle(self) -> Self {
+        match self {
+            WindowType::Master => WindowType::Splace,
+            WindowType::Splace => WindowType::Master,
+        }
+    }
+}
+
+/// What gets rendered in the body
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Screen {
+    /// One chart per watched coin
+    #[default]
+    Charts,
+    /// Every watched coin in a single chart, normalized to the percent change
+    Comparison,
+    /// Table with the numbers of every watched coin
+    Overview,
+    /// Treemap of the watched coins, sized by volume and colored by their 24h change
+    Heatmap,
+    /// Holdings and their live value
+    Portfolio,
+    /// Cost basis and P&L of the imported transactions
+    Ledger,
+    /// Alert rules and the alerts that fired
+    Alerts,
+    /// Spreads between the quotes of the same base asset
+    Arbitrage,
+}
+
+/// Splits the body into one rect per watched coin. The returned rects are in the same order as the
+/// coins, so `rects[i]` belongs to `watching[i]`.
+fn calc_body_layout(
+    area: Rect,
+    amount: usize,
+    focused: usize,
+    window_type: WindowType,
+) -> Vec<Rect> {
     if amount == 1 {
         return Layout::vertical([Constraint::Percentage(100)])
             .areas::<1>(area)
@@ -64,15 +123,11 @@ fn calc_body_layout(area: Rect, amount: usize, window_type: WindowType) -> Vec<R
     }
 
     match window_type {
-        // WindowType::Master => todo!(),
         WindowType::Splace => {
             // flooring here so we can get the max fitable without any rendering problems
             let w_max = (area.width as f32 / BODY_MIN_W as f32)
                 .floor()
                 .min(amount as f32);
-            let h_max = ((area.height as f32 / BODY_MIN_H as f32).floor()).min(amount as f32);
-
-            let can_fit = h_max * w_max;
 
             // check if we can have an equal amount on the horizontal and vertical
             let [w_act, h_act] = if (amount as f64).sqrt() == (amount as f64).sqrt().floor() {
@@ -110,13 +165,39 @@ fn calc_body_layout(area: Rect, amount: usize, window_type: WindowType) -> Vec<R
                 filled_spots += w_act;
             }
 
-            ret_rects.iter().map(|i| i.to_owned()).flatten().collect()
+            ret_rects.iter().flat_map(|i| i.to_owned()).collect()
         }
         WindowType::Master => {
-            let [left, right] =
+            let [master, stack] =
                 Layout::horizontal([Constraint::Fill(2), Constraint::Fill(1)]).areas(area);
 
-            vec![left, right]
+            // when the stack gets too crowded we open up another column next to it instead of
+            // squishing the charts until they are unreadable
+            let others = amount - 1;
+            let rows_fit = (stack.height as i32 / BODY_MIN_H).max(1) as usize;
+            let cols = others.div_ceil(rows_fit);
+            let rows = others.div_ceil(cols);
+
+            let mut stack_rects = Layout::horizontal(vec![Constraint::Fill(1); cols])
+                .split(stack)
+                .iter()
+                .flat_map(|col| {
+                    Layout::vertical(vec![Constraint::Fill(1); rows])
+                        .split(*col)
+                        .to_vec()
+                })
+                .collect::<Vec<Rect>>()
+                .into_iter();
+
+            (0..amount)
+                .map(|i| {
+                    if i == focused {
+                        master
+                    } else {
+                        stack_rects.next().unwrap_or_default()
+                    }
+                })
+                .collect()
         }
     }
 }
@@ -136,18 +217,80 @@ pub struct App {
 
     /// Should the borders of the charts be animated or not
     pub border_animation: bool,
+    /// The config merged with the command line
+    pub settings: Settings,
 
-    /// this is a value that gets changed every tick by 1 plus or minus.
-    /// this does have bounds tho, from 240-255
-    color: u8,
-    /// this decides if we add or subtract
-    color_add: bool,
+    /// The last price of every product, to see if it moved since the last tick
+    last_prices: HashMap<String, f64>,
+    /// Unix ms when the price of a product last moved and if it went up, the border pulses
+    pulses: HashMap<String, (i64, bool)>,
+    /// Unix ms of the last drawn frame, the turning borders only need a few frames per second
+    rotated_at: i64,
 
     /// Price mulitplier
     price_mult: HashMap<String, f64>,
 
     /// The current selected chart/ window
-    active_window: i32,
+    active_window: usize,
+    /// How the charts are arranged in the body
+    window_type: WindowType,
+    /// Only render the selected chart, over the whole body
+    maximized: bool,
+    /// The screen that is currently shown
+    screen: Screen,
+    /// The indicators that are drawn on top of the chart, per coin
+    indicators: HashMap<String, PanelIndicators>,
+    /// Selection and sorting of the overview table
+    overview: OverviewState,
+    /// The holdings from the portfolio file
+    pub portfolio: Portfolio,
+    /// The imported transactions
+    pub ledger: Ledger,
+    /// How the cost basis of the ledger is calculated
+    pub cost_basis: CostBasisMethod,
+    /// The alert rules and their history
+    pub alerts: AlertEngine,
+    /// Last error of an alert edit, shown in the alerts screen
+    alert_error: Option<String>,
+    /// Unix ms until which the border of a coin flashes, because one of its alerts fired
+    flashing: HashMap<String, i64>,
+    /// The text input, when its open every key goes to it
+    prompt: Option<Prompt>,
+    /// Currency the charts get converted to, `None` shows them in their quote currency
+    pub display_currency: Option<String>,
+    /// Every product of the exchange, empty if the list couldnt be loaded
+    pub catalog: Catalog,
+    /// The product search popup
+    picker: Option<Picker>,
+    /// Changes the subscription of the running feed
+    pub feed: Option<mpsc::UnboundedSender<FeedCommand>>,
+    /// Products that are subscribed for the portfolio, alerts etc. and must stay subscribed when
+    /// the watchlist changes
+    pub pinned_products: Vec<String>,
+    /// The watchlist that is shown, `None` if the products came from `--watching`
+    pub watchlist: Option<String>,
+    /// Spreads between the quotes of the same base asset
+    pub arbitrage: ArbitrageMonitor,
+    /// Reloads the config when the file changes, `None` if it couldnt be watched
+    pub config_watch: Option<ConfigWatch>,
+    /// Message in the corner of the body, like a broken config
+    pub toast: Option<Toast>,
+    /// Show the keybindings over everything
+    help: bool,
+    /// The start of a key sequence like `g h`
+    pending_keys: Vec<KeyBinding>,
+    /// History and tab completion of the command line
+    pub commands: CommandLine,
+    /// Something changed since the last frame
+    dirty: bool,
+    /// The message count and the second of the clock at the last frame, both change the picture
+    drawn_messages: u64,
+    drawn_second: i64,
+    frame_stats: FrameStats,
+    /// Show the frame timings over the body
+    show_frame_stats: bool,
+    /// The meme in the header and footer
+    banner: Banner,
 }
 
 impl Default for App {
@@ -158,31 +301,62 @@ impl Default for App {
             events: EventHandler::new(),
             start_time: Self::now(),
             border_animation: true,
-            color: 255,
-            color_add: false,
+            settings: Settings::default(),
+            last_prices: HashMap::new(),
+            pulses: HashMap::new(),
+            rotated_at: 0,
             price_mult: HashMap::from([("SOL-USD".to_string(), 0.5)]),
             active_window: 0,
+            window_type: WindowType::default(),
+            maximized: false,
+            screen: Screen::default(),
+            indicators: HashMap::new(),
+            overview: OverviewState::default(),
+            portfolio: Portfolio::default(),
+            ledger: Ledger::default(),
+            cost_basis: CostBasisMethod::default(),
+            alerts: AlertEngine::default(),
+            alert_error: None,
+            flashing: HashMap::new(),
+            prompt: None,
+            display_currency: None,
+            catalog: Catalog::default(),
+            picker: None,
+            feed: None,
+            pinned_products: vec![],
+            watchlist: None,
+            arbitrage: ArbitrageMonitor::default(),
+            config_watch: None,
+            toast: None,
+            help: false,
+            pending_keys: vec![],
+            commands: CommandLine::default(),
+            dirty: true,
+            drawn_messages: 0,
+            drawn_second: 0,
+            frame_stats: FrameStats::default(),
+            show_frame_stats: false,
+            banner: Banner::new(Self::now()),
         }
     }
 }
 
 impl App {
     /// Constructs a new instance of [`App`].
-    pub fn new(watching: Option<Vec<String>>) -> Self {
-        match watching {
-            Some(v) => {
-                let prepd = v
-                    .iter()
-                    .map(|f| (f.to_owned(), 0.5f64))
-                    .collect::<Vec<(String, f64)>>();
-
-                Self {
-                    watching: v,
-                    price_mult: prepd.into_iter().collect(),
-                    ..Default::default()
-                }
-            }
-            _ => Self::default(),
+    pub fn new(settings: Settings) -> Self {
+        Self {
+            watching: settings.watching.clone(),
+            price_mult: settings
+                .watching
+                .iter()
+                .map(|f| (f.to_owned(), 0.5f64))
+                .collect(),
+            events: EventHandler::with_rates(settings.tick_rate_ms, settings.frame_rate),
+            window_type: settings.layout,
+            watchlist: settings.watchlist.clone(),
+            border_animation: settings.border_animation,
+            settings,
+            ..Default::default()
         }
     }
 
@@ -196,8 +370,8 @@ impl App {
     fn get_coin_mult<T: Display>(&self, coin: T) -> f64 {
         self.price_mult
             .get(&coin.to_string())
-            .unwrap_or(&1.0)
-            .clone()
+            .copied()
+            .unwrap_or(1.0)
     }
 
     fn get_coin_mult_mut<T: Display>(&mut self, coin: T) -> &mut f64 {
@@ -209,91 +383,331 @@ impl App {
 
     /// Run the application's main loop.
     pub async fn run(mut self, mut terminal: DefaultTerminal) -> color_eyre::Result<()> {
-        let mut rng = XorShift32::new(
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .expect("time went backwards")
-                .as_secs(),
-        );
+        self.update_banner();
+        while self.running {
+            if self.needs_redraw() {
+                let started = Instant::now();
+                terminal.draw(|frame| {
+                    // text without its own color uses the label color of the theme
+                    let theme = &self.settings.theme;
+                    let (top_text, bottom_text) = self.banner.text(&self.settings.memes);
+                    frame.render_widget(theme.background_block(), frame.area());
+                    if self.watching.is_empty() {
+                        frame.render_widget(
+                            Text::from("You dont have any Coins selected").centered(),
+                            frame.area(),
+                        );
+                        return;
+                    }
+                    // TODO: add layouts for different screen sizes and for the amount of chains to
+                    // watch
+                    let [top, body, bottom] = Layout::vertical([
+                        Constraint::Length(2),
+                        Constraint::Fill(1),
+                        Constraint::Length(1),
+                    ])
+                    .areas(frame.area());
 
-        let (top_text, bottom_text) = MEMES
-            .get(rng.gen_range(MEMES.len() as usize) as usize)
-            .unwrap_or(&("hellol", "byel"));
+                    let now =
+                        convert_timestamp_to_locale(chrono::Local::now().timestamp_millis() as f64);
 
-        while self.running {
-            terminal.draw(|frame| {
-                if self.watching.len() == 0 {
+                    let now = match &self.watchlist {
+                        Some(name) => format!("{now} - {name}"),
+                        None => now,
+                    };
                     frame.render_widget(
-                        Text::from("You dont have any Coins selected").centered(),
-                        frame.area(),
+                        Paragraph::new(format!("{top_text}\n{}", now)).centered(),
+                        top,
                     );
-                    return;
-                }
-                // TODO: add layouts for different screen sizes and for the amount of chains to
-                // watch
-                let [top, body, bottom] = Layout::vertical([
-                    Constraint::Length(2),
-                    Constraint::Fill(1),
-                    Constraint::Length(1),
-                ])
-                .areas(frame.area());
-
-                let now =
-                    convert_timestamp_to_locale(chrono::Local::now().timestamp_millis() as f64);
-
-                frame.render_widget(
-                    Paragraph::new(format!("{top_text}\n{}", now)).centered(),
-                    top,
-                );
-                frame.render_widget(Line::from(*bottom_text).centered(), bottom);
-
-                let layout: Vec<Rect> =
-                    calc_body_layout(body, self.watching.len(), WindowType::Splace);
+                    match &self.prompt {
+                        Some(p) => p.render(frame, bottom, &self.settings.theme),
+                        // the start of a key sequence, so its clear that the next key is waited for
+                        None if !self.pending_keys.is_empty() => frame.render_widget(
+                            Line::from(format!("{} ...", KeySequence(self.pending_keys.clone())))
+                                .fg(self.settings.theme.warning)
+                                .centered(),
+                            bottom,
+                        ),
+                        None => frame.render_widget(Line::from(bottom_text).centered(), bottom),
+                    }
 
-                for (i, v) in layout.iter().enumerate() {
-                    if i >= self.watching.len() {
-                        continue;
+                    self.render_body(frame, body);
+                    if let Some(t) = &self.toast {
+                        render_toast(frame, body, t, &self.settings.theme);
                     }
-                    self.render_chart(frame, v.to_owned(), self.watching[i].clone(), 60000.0);
-                }
-            })?;
-
-            match self.events.next().await? {
-                Event::Tick => self.tick(),
-                Event::Crossterm(event) => match event {
-                    crossterm::event::Event::Key(key_event) => self.handle_key_events(key_event)?,
-                    _ => {}
-                },
-                Event::App(app_event) => match app_event {
-                    AppEvent::Quit => self.quit(),
-                    AppEvent::IncMult(fine) => {
-                        let v = self
-                            .get_coin_mult_mut(self.watching[self.active_window as usize].clone());
-                        if fine {
-                            *v += 0.01;
-                        } else {
-                            *v += 0.1;
-                        }
+                    if let Some(p) = &self.prompt
+                        && p.target == PromptTarget::Command
+                    {
+                        self.commands.render_palette(
+                            frame,
+                            body,
+                            &p.text,
+                            &self.catalog,
+                            &self.watching,
+                            &self.settings.keymap,
+                            &self.settings.theme,
+                        );
+                    }
+                    if let Some(p) = &self.picker {
+                        p.render(
+                            frame,
+                            body,
+                            &self.catalog,
+                            &self.watching,
+                            &self.settings.theme,
+                        );
+                    }
+                    if self.help {
+                        render_help(
+                            frame,
+                            body,
+                            &self.settings.keymap,
+                            self.mode(),
+                            &self.settings.theme,
+                        );
+                    }
+                    if self.show_frame_stats {
+                        render_frame_stats(
+                            frame,
+                            body,
+                            &self.frame_stats,
+                            self.settings.frame_rate,
+                            &self.settings.theme,
+                        );
                     }
-                    AppEvent::DecMult(fine) => {
-                        let v = self
-                            .get_coin_mult_mut(self.watching[self.active_window as usize].clone());
-                        if fine {
-                            *v -= 0.01;
-                        } else {
-                            *v -= 0.1;
+                    // last, so it covers everything that got drawn
+                    degrade(
+                        frame.buffer_mut(),
+                        self.settings.colors,
+                        self.settings.borders,
+                    );
+                })?;
+                self.frame_stats.record(started);
+                self.dirty = false;
+            } else {
+                self.frame_stats.skipped += 1;
+            }
+
+            // wait for the next frame, everything that comes in until then is handled first
+            loop {
+                match self.events.next().await? {
+                    Event::Render => {
+                        // input that is already waiting goes into this frame instead of the next
+                        while let Some(event) = self.events.try_next() {
+                            if !matches!(event, Event::Render) {
+                                self.frame_stats.early_events += 1;
+                                self.handle_event(event)?;
+                            }
                         }
+                        break;
                     }
-                    _ => {}
-                },
+                    event => self.handle_event(event)?,
+                }
+                if !self.running {
+                    break;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Applies an event to the state, anything besides a tick changes what is shown
+    fn handle_event(&mut self, event: Event) -> color_eyre::Result<()> {
+        if !matches!(event, Event::Tick | Event::Render) {
+            self.dirty = true;
+        }
+        match event {
+            Event::Render => {}
+            Event::Tick => self.tick(),
+            Event::Crossterm(event) => {
+                if let crossterm::event::Event::Key(key_event) = event {
+                    self.handle_key_events(key_event)?
+                }
             }
+            Event::App(app_event) => match app_event {
+                AppEvent::Quit => self.quit(),
+                AppEvent::IncMult(fine) => {
+                    let v = self.get_coin_mult_mut(self.watching[self.active_window].clone());
+                    if fine {
+                        *v += 0.01;
+                    } else {
+                        *v += 0.1;
+                    }
+                }
+                AppEvent::DecMult(fine) => {
+                    let v = self.get_coin_mult_mut(self.watching[self.active_window].clone());
+                    if fine {
+                        *v -= 0.01;
+                    } else {
+                        *v -= 0.1;
+                    }
+                }
+                AppEvent::FocusNext => {
+                    self.active_window = (self.active_window + 1) % self.watching.len().max(1);
+                }
+                AppEvent::FocusPrev => {
+                    let len = self.watching.len().max(1);
+                    self.active_window = (self.active_window + len - 1) % len;
+                }
+                AppEvent::ToggleMaximize => self.maximized = !self.maximized,
+                AppEvent::CycleLayout => self.window_type = self.window_type.cycle(),
+                AppEvent::ToggleIndicator(kind) => self.active_panel().toggle(kind),
+                AppEvent::CycleOscillator => self.active_panel().cycle_oscillator(),
+                AppEvent::SelectIndicator => self.active_panel().select_next(),
+                AppEvent::ChangeIndicatorPeriod(by) => self.active_panel().change_period(by),
+                AppEvent::OverviewSelect(by) => {
+                    let len = self.overview.rows(&self.watching).len();
+                    self.overview.select(by, len);
+                }
+                AppEvent::OverviewSort(reverse) => self.overview.sort(reverse),
+                AppEvent::OpenChart(product) => {
+                    if let Some(idx) = self.watching.iter().position(|w| *w == product) {
+                        self.active_window = idx;
+                        self.maximized = true;
+                        self.screen = Screen::Charts;
+                    }
+                }
+                AppEvent::CycleCostBasis => self.cost_basis = self.cost_basis.cycle(),
+                AppEvent::ToggleScreen(screen) => {
+                    self.screen = if self.screen == screen {
+                        Screen::Charts
+                    } else {
+                        screen
+                    };
+                }
+                AppEvent::AlertSelect(by) => self.alerts.select(by),
+                AppEvent::AlertDelete => {
+                    self.alert_error = self.alerts.delete_selected().err().map(|e| e.to_string());
+                }
+                AppEvent::AlertSnooze => self.alerts.snooze_selected(),
+                AppEvent::AlertToggleRepeat => {
+                    self.alert_error = self
+                        .alerts
+                        .toggle_repeat_selected()
+                        .err()
+                        .map(|e| e.to_string());
+                }
+                AppEvent::OpenPrompt(target, text) => {
+                    self.commands.reset();
+                    self.prompt = Some(Prompt::new(target, text));
+                }
+                AppEvent::SubmitPrompt(target, text) => self.submit_prompt(target, &text),
+                AppEvent::OpenPicker => self.picker = Some(Picker::default()),
+                AppEvent::AddProduct(product) => self.add_product(product),
+                AppEvent::NextWatchlist => self.next_watchlist(),
+                AppEvent::ReloadConfig => self.reload_config(),
+                AppEvent::ToggleHelp => self.help = !self.help,
+                AppEvent::ToggleFrameStats => self.show_frame_stats = !self.show_frame_stats,
+                AppEvent::ToggleAnimation => {
+                    self.border_animation = !self.border_animation;
+                    self.toast = Some(Toast::info(match self.border_animation {
+                        true => "Border animation on",
+                        false => "Border animation off",
+                    }));
+                }
+                _ => {}
+            },
         }
         Ok(())
     }
 
-    fn render_chart(&self, frame: &mut Frame, area: Rect, coin: String, t_changee: f64) {
-        // add filtering for coins
+    /// Renders the current screen into the body
+    fn render_body(&self, frame: &mut Frame, body: Rect) {
+        match self.screen {
+            Screen::Comparison => {
+                render_comparison(
+                    frame,
+                    body,
+                    &self.watching,
+                    self.settings.t_change(),
+                    &self.settings.theme,
+                );
+                return;
+            }
+            Screen::Overview => {
+                render_overview(
+                    frame,
+                    body,
+                    &self.watching,
+                    &self.overview,
+                    &self.settings.theme,
+                );
+                return;
+            }
+            Screen::Heatmap => {
+                render_heatmap(frame, body, &self.watching, &self.settings.theme);
+                return;
+            }
+            Screen::Portfolio => {
+                render_portfolio(frame, body, &self.portfolio, &self.settings.theme);
+                return;
+            }
+            Screen::Ledger => {
+                render_ledger(
+                    frame,
+                    body,
+                    &self.ledger,
+                    self.cost_basis,
+                    &self.portfolio.quote,
+                    &self.settings.theme,
+                );
+                return;
+            }
+            Screen::Arbitrage => {
+                let quote = self.display_currency.as_deref().unwrap_or("USD");
+                render_arbitrage(frame, body, &self.arbitrage, quote, &self.settings.theme);
+                return;
+            }
+            Screen::Alerts => {
+                render_alerts(
+                    frame,
+                    body,
+                    &self.alerts,
+                    self.alert_error.as_deref(),
+                    &self.settings.keymap,
+                    &self.settings.theme,
+                );
+                return;
+            }
+            Screen::Charts => {}
+        }
 
+        if self.maximized {
+            let coin = self.watching[self.active_window].clone();
+            self.render_chart(frame, body, coin, self.settings.t_change(), true);
+            return;
+        }
+
+        let layout: Vec<Rect> = calc_body_layout(
+            body,
+            self.watching.len(),
+            self.active_window,
+            self.window_type,
+        );
+
+        for (i, v) in layout.iter().enumerate() {
+            if i >= self.watching.len() {
+                continue;
+            }
+            self.render_chart(
+                frame,
+                v.to_owned(),
+                self.watching[i].clone(),
+                self.settings.t_change(),
+                i == self.active_window,
+            );
+        }
+    }
+
+    fn render_chart(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        coin: String,
+        t_changee: f64,
+        focused: bool,
+    ) {
+        let theme = &self.settings.theme;
         let tmp_data = match ws_messages.lock().clone().get(&coin) {
             Some(v) => v
                 .iter()
@@ -303,13 +717,24 @@ impl App {
             None => return,
         };
 
+        // convert to the display currency with the current rate, until the feed has a rate the
+        // chart stays in its quote currency
+        let quote = Currency::of_product(&coin);
+        let (crc, rate) = match &self.display_currency {
+            Some(d) => match rate(&quote.code, d) {
+                Some(r) => (Currency::lookup(d), r),
+                None => (quote, 1.0),
+            },
+            None => (quote, 1.0),
+        };
+
         let data = tmp_data
             .iter()
             .map(|i| {
                 let time = i.time.parse::<DateTime<chrono::Utc>>().unwrap();
                 (
                     time.timestamp_millis() as f64,
-                    i.price.parse::<f64>().unwrap_or(0.0),
+                    i.price.parse::<f64>().unwrap_or(0.0) * rate,
                 )
             })
             .collect::<Vec<(f64, f64)>>();
@@ -319,13 +744,15 @@ impl App {
         let price = last.1;
 
         //                                                                  TIME AXIS
-        let x_axis = Axis::default()
-            .style(Color::White)
-            .bounds([now - t_changee * 5.0, now + t_changee])
-            .labels([
-                convert_timestamp_to_locale(now - t_changee * 5.0).white(),
-                convert_timestamp_to_locale(now + t_changee).white(),
-            ]);
+        let x_bounds = [now - t_changee * 5.0, now + t_changee];
+        let x_start_label = convert_timestamp_to_locale(now - t_changee * 5.0);
+        // the first time label hangs over to the left of the price axis, so the oscillator pane
+        // needs to know about it to line up
+        let x_label_overhang = x_start_label.chars().count().saturating_sub(1);
+        let x_axis = Axis::default().style(theme.axis).bounds(x_bounds).labels([
+            x_start_label.fg(theme.label),
+            convert_timestamp_to_locale(now + t_changee).fg(theme.label),
+        ]);
 
         let price_1per = price / 100.0;
 
@@ -340,102 +767,667 @@ impl App {
             None => return,
         }; */
 
-        let crc = match coin.split('-').collect::<Vec<&str>>()[1] {
-            "EUR" => CURRENCIES[1],
-            _ => CURRENCIES[0], // default to $
-        };
-
         //                                                                  PRICE AXIS
+        let dec = crc.decimals_for(price);
+        let y_labels = [
+            format!("{}{:.dec$}", crc.format(lo), lo - price),
+            crc.format(price),
+            format!("{}+{:.dec$}", crc.format(hi), hi - price),
+        ];
+        let label_width = y_labels
+            .iter()
+            .map(|l| l.chars().count())
+            .max()
+            .unwrap_or(0)
+            .max(x_label_overhang);
+        let [lo_label, price_label, hi_label] = y_labels;
+
         let y_axis = Axis::default()
             .bounds([lo, hi])
             .labels([
-                format!("{crc}{:.2}{:.2}", lo, lo - price).red(),
-                format!("{crc}{price}").white(),
-                format!("{crc}{:.2}+{:.2}", hi, hi - price).green(),
+                lo_label.fg(theme.down),
+                price_label.fg(theme.label),
+                hi_label.fg(theme.up),
             ])
-            .style(Color::White);
+            .style(theme.axis);
 
         /* let data = (1..1000)
         .map(|i: u64| (i as f64, i.pow(2) as f64))
         .collect::<Vec<(f64, f64)>>(); */
 
-        let buys = tmp_data.iter().filter(|f| f.side == "buy").count();
+        // The line is colored by whoever traded more volume in the pressure window
+        let pressure = Pressure::calc(
+            &tmp_data,
+            now - self.settings.pressure_window as f64 * 1000.0,
+        );
+        let color = pressure.color(theme.up, theme.down);
+        let scale = |points: Vec<(f64, f64)>| {
+            points
+                .into_iter()
+                .map(|(t, v)| (t, v * rate))
+                .collect::<Vec<(f64, f64)>>()
+        };
+        let (buy_prints, sell_prints) = prints(&tmp_data, x_bounds[0]);
+        let (buy_prints, sell_prints) = (scale(buy_prints), scale(sell_prints));
 
-        // If we have an overall surpluss of buys, we display it green to show the past 5k request
-        // bias
-        let color = if buys > tmp_data.len() / 2 {
-            Color::Rgb(0, 255, 100)
+        let name = match rate == 1.0 {
+            true => coin.clone(),
+            false => format!("{coin} in {}", crc.code),
+        };
+        let title = if focused {
+            format!("> {} - {} <", name, tmp_data.len())
         } else {
-            Color::Rgb(255, 0, 100)
+            format!("{} - {}", name, tmp_data.len())
         };
 
-        let title = format!("{} - {}", coin, tmp_data.len());
+        let indicator_lines: Vec<IndicatorLine> = self
+            .indicators
+            .get(&coin)
+            .map(|p| {
+                let mark = p.indicators.len() > 1;
+                p.indicators
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(idx, i)| i.datasets(mark && idx == p.selected))
+                    .map(|(name, color, points)| (name, color, scale(points)))
+                    .collect()
+            })
+            .unwrap_or(vec![]);
 
-        let chart = Chart::new(vec![
+        let mut datasets = vec![
             Dataset::default()
                 .style(color)
-                .marker(symbols::Marker::Braille)
+                .marker(theme.marker)
                 .data(&data),
-        ])
-        .x_axis(x_axis)
-        .y_axis(y_axis);
+            Dataset::default()
+                .style(theme.up)
+                .marker(symbols::Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .data(&buy_prints),
+            Dataset::default()
+                .style(theme.down)
+                .marker(symbols::Marker::Dot)
+                .graph_type(GraphType::Scatter)
+                .data(&sell_prints),
+        ];
+        datasets.extend(indicator_lines.iter().map(|(name, color, points)| {
+            Dataset::default()
+                .name(name.clone())
+                .style(*color)
+                .marker(theme.marker)
+                .data(points)
+        }));
+
+        let chart = Chart::new(datasets).x_axis(x_axis).y_axis(y_axis);
+
+        let oscillator = self
+            .indicators
+            .get(&coin)
+            .and_then(|p| p.oscillator.as_ref());
+        let oscillator_lines = oscillator.map(|o| o.lines(x_bounds)).unwrap_or_default();
+        let pane = oscillator.map(|o| o.chart(x_bounds, label_width, &oscillator_lines, theme));
 
         let c = coin.split('-').collect::<Vec<&str>>()[0];
-        let widget = GradientWrapper::new(chart).title(title).gradient_colors(
-            CRYPTO_COLOR_CODES
-                .get(c)
-                .unwrap_or(&GradientConfig::default())
-                .clone(),
+        let body = WithPressure::new(
+            pressure.gauge(self.settings.pressure_window, theme.up, theme.down),
+            WithOscillator::new(chart, pane),
         );
+        // blink between red and the normal colors while an alert of the coin is fresh
+        let now = chrono::Local::now().timestamp_millis();
+        let flashing = self.flashing.get(&coin).is_some_and(|until| *until > now);
+        let gradient = if flashing && (now / 500) % 2 == 0 {
+            GradientConfig::new_1(theme.error)
+        } else {
+            theme.coin_gradient(c)
+        };
+        let mut widget = GradientWrapper::new(body)
+            .title(title)
+            .title_color(theme.label)
+            .border_symbols(theme.borders)
+            .gradient_colors(gradient);
+        // both only depend on the clock, so they look the same at every frame rate
+        if self.border_animation && !flashing {
+            widget = widget.rotation((now % ROTATION_MS) as f32 / ROTATION_MS as f32);
+            if let Some((at, up)) = self.pulses.get(&coin)
+                && now - at < PULSE_MS
+            {
+                let fade = 1.0 - (now - at) as f32 / PULSE_MS as f32;
+                let color = if *up { theme.up } else { theme.down };
+                widget = widget.pulse(color, PULSE_STRENGTH * fade);
+            }
+        }
         frame.render_widget(widget, area);
     }
 
+    /// Which keybindings are active
+    fn mode(&self) -> Mode {
+        if self.picker.is_some() || self.prompt.is_some() {
+            Mode::Command
+        } else if matches!(self.screen, Screen::Overview | Screen::Alerts) {
+            Mode::Cursor
+        } else {
+            Mode::Normal
+        }
+    }
+
+    /// Something changed since the last frame, like new messages, the clock or a moving border
+    fn needs_redraw(&mut self) -> bool {
+        let messages = message_count();
+        let second = chrono::Local::now().timestamp();
+        let changed = messages != self.drawn_messages || second != self.drawn_second;
+        self.drawn_messages = messages;
+        self.drawn_second = second;
+
+        let now = chrono::Local::now().timestamp_millis();
+        // the slow turning of the borders alone doesnt need the full frame rate
+        let turning = self.border_animation
+            && self.screen == Screen::Charts
+            && !self.watching.is_empty()
+            && now - self.rotated_at >= 1000 / ROTATION_FPS;
+        let redraw = self.dirty || changed || turning || self.animating(now);
+        if redraw {
+            self.rotated_at = now;
+        }
+        redraw
+    }
+
+    /// The borders of the charts change on every frame while a price pulses or an alert flashes
+    fn animating(&self, now: i64) -> bool {
+        if self.screen != Screen::Charts || self.watching.is_empty() {
+            return false;
+        }
+        let flashing = self.flashing.values().any(|until| *until > now);
+        let pulsing = self.border_animation
+            && self.watching.iter().any(|p| {
+                self.pulses
+                    .get(p)
+                    .is_some_and(|(at, _)| now - at < PULSE_MS)
+            });
+        flashing || pulsing
+    }
+
     /// Handles the key events and updates the state of [`App`].
     pub fn handle_key_events(&mut self, key_event: KeyEvent) -> color_eyre::Result<()> {
-        let is_shift = key_event.modifiers == KeyModifiers::SHIFT;
-        let is_ctrl = key_event.modifiers == KeyModifiers::CONTROL;
-        let is_alt = key_event.modifiers == KeyModifiers::ALT;
+        // ctrl-c always quits, no matter what the keymap says
+        if key_event.modifiers == KeyModifiers::CONTROL
+            && matches!(key_event.code, KeyCode::Char('c' | 'C'))
+        {
+            self.events.send(AppEvent::Quit);
+            return Ok(());
+        }
+        // any key closes the help
+        if self.help {
+            self.help = false;
+            return Ok(());
+        }
+
+        let mode = self.mode();
+        self.pending_keys.push(KeyBinding::from_event(key_event));
+        let action = match self.settings.keymap.lookup(mode, &self.pending_keys) {
+            Lookup::Pending => return Ok(()),
+            Lookup::Action(action) => Some(action),
+            // the sequence went nowhere, the last key might still do something on its own
+            Lookup::None if self.pending_keys.len() > 1 => {
+                self.pending_keys.clear();
+                return self.handle_key_events(key_event);
+            }
+            Lookup::None => None,
+        };
+        self.pending_keys.clear();
 
-        match key_event.code {
-            KeyCode::Esc | KeyCode::Char('q') => self.events.send(AppEvent::Quit),
-            KeyCode::Char('c' | 'C') if is_ctrl => self.events.send(AppEvent::Quit),
-            KeyCode::Up => self.events.send(AppEvent::IncMult(is_shift)),
-            KeyCode::Down => self.events.send(AppEvent::DecMult(is_shift)),
-            _ => {}
+        if mode == Mode::Command {
+            self.handle_command_key(action, key_event);
+        } else if let Some(event) = action.and_then(|a| self.action_event(a)) {
+            self.events.send(event);
         }
         Ok(())
     }
 
+    /// Keys while the prompt or the picker is open, everything that isnt bound is typed
+    fn handle_command_key(&mut self, action: Option<Action>, key_event: KeyEvent) {
+        if let Some(picker) = self.picker.as_mut() {
+            match action {
+                Some(Action::Submit) => {
+                    let product = picker.submit(&self.catalog);
+                    self.picker = None;
+                    if let Some(product) = product {
+                        self.events.send(AppEvent::AddProduct(product));
+                    }
+                }
+                Some(Action::Cancel) => self.picker = None,
+                Some(Action::SelectUp) => picker.select(-1, &self.catalog),
+                Some(Action::SelectDown) => picker.select(1, &self.catalog),
+                _ => picker.handle_key(key_event),
+            }
+            return;
+        }
+
+        if let Some(prompt) = self.prompt.as_mut() {
+            let command = prompt.target == PromptTarget::Command;
+            match action {
+                Some(Action::Submit) => {
+                    let (target, text) = (prompt.target, prompt.text.trim().to_string());
+                    self.prompt = None;
+                    self.events.send(AppEvent::SubmitPrompt(target, text));
+                }
+                Some(Action::Cancel) => self.prompt = None,
+                Some(Action::SelectUp) if command => self.commands.history_up(&mut prompt.text),
+                Some(Action::SelectDown) if command => self.commands.history_down(&mut prompt.text),
+                Some(Action::Complete) if command => {
+                    self.commands
+                        .complete(&mut prompt.text, &self.catalog, &self.watching)
+                }
+                _ => {
+                    prompt.handle_key(key_event);
+                    self.commands.typed();
+                }
+            }
+        }
+    }
+
+    /// The indicators of the focused chart, with the ones of the config if it has none yet
+    fn active_panel(&mut self) -> &mut PanelIndicators {
+        let coin = self.watching[self.active_window].clone();
+        let defaults = &self.settings.indicators;
+        self.indicators
+            .entry(coin)
+            .or_insert_with(|| PanelIndicators::new(defaults))
+    }
+
+    /// What an action of normal or cursor mode does on the current screen
+    fn action_event(&self, action: Action) -> Option<AppEvent> {
+        let overview_len = || self.overview.rows(&self.watching).len();
+        let indicator = |name| -> IndicatorKind { self.settings.indicators.kind(name) };
+        let event = match (action, self.screen) {
+            (Action::Quit, _) => AppEvent::Quit,
+            (Action::Help, _) => AppEvent::ToggleHelp,
+            (Action::CommandLine, _) => AppEvent::OpenPrompt(PromptTarget::Command, String::new()),
+            (Action::Overview, _) => AppEvent::ToggleScreen(Screen::Overview),
+            (Action::Heatmap, _) => AppEvent::ToggleScreen(Screen::Heatmap),
+            (Action::Portfolio, _) => AppEvent::ToggleScreen(Screen::Portfolio),
+            (Action::Ledger, _) => AppEvent::ToggleScreen(Screen::Ledger),
+            (Action::CycleCostBasis, _) => AppEvent::CycleCostBasis,
+            (Action::Alerts, _) => AppEvent::ToggleScreen(Screen::Alerts),
+            (Action::Arbitrage, _) => AppEvent::ToggleScreen(Screen::Arbitrage),
+            (Action::Comparison, _) => AppEvent::ToggleScreen(Screen::Comparison),
+            (Action::AddProduct, _) => AppEvent::OpenPicker,
+            (Action::NextWatchlist, _) => AppEvent::NextWatchlist,
+            (Action::IncMult, _) => AppEvent::IncMult(false),
+            (Action::IncMultFine, _) => AppEvent::IncMult(true),
+            (Action::DecMult, _) => AppEvent::DecMult(false),
+            (Action::DecMultFine, _) => AppEvent::DecMult(true),
+            (Action::FocusNext, _) => AppEvent::FocusNext,
+            (Action::FocusPrev, _) => AppEvent::FocusPrev,
+            (Action::ToggleMaximize, _) => AppEvent::ToggleMaximize,
+            (Action::CycleLayout, _) => AppEvent::CycleLayout,
+            (Action::ToggleSma, _) => AppEvent::ToggleIndicator(indicator(IndicatorName::Sma)),
+            (Action::ToggleEma, _) => AppEvent::ToggleIndicator(indicator(IndicatorName::Ema)),
+            (Action::ToggleVwap, _) => AppEvent::ToggleIndicator(indicator(IndicatorName::Vwap)),
+            (Action::ToggleBollinger, _) => {
+                AppEvent::ToggleIndicator(indicator(IndicatorName::Bollinger))
+            }
+            (Action::CycleOscillator, _) => AppEvent::CycleOscillator,
+            (Action::SelectIndicator, _) => AppEvent::SelectIndicator,
+            (Action::PeriodDown, _) => AppEvent::ChangeIndicatorPeriod(-5),
+            (Action::PeriodUp, _) => AppEvent::ChangeIndicatorPeriod(5),
+            (Action::ToggleAnimation, _) => AppEvent::ToggleAnimation,
+            (Action::ToggleFrameStats, _) => AppEvent::ToggleFrameStats,
+
+            (Action::SelectUp, Screen::Overview) => AppEvent::OverviewSelect(-1),
+            (Action::SelectDown, Screen::Overview) => AppEvent::OverviewSelect(1),
+            (Action::SelectFirst, Screen::Overview) => {
+                AppEvent::OverviewSelect(-(self.overview.selected as isize))
+            }
+            (Action::SelectLast, Screen::Overview) => AppEvent::OverviewSelect(
+                overview_len() as isize - 1 - self.overview.selected as isize,
+            ),
+            (Action::Open, Screen::Overview) => {
+                let rows = self.overview.rows(&self.watching);
+                AppEvent::OpenChart(rows.get(self.overview.selected)?.product.clone())
+            }
+            (Action::Sort, Screen::Overview) => AppEvent::OverviewSort(false),
+            (Action::SortReverse, Screen::Overview) => AppEvent::OverviewSort(true),
+
+            (Action::SelectUp, Screen::Alerts) => AppEvent::AlertSelect(-1),
+            (Action::SelectDown, Screen::Alerts) => AppEvent::AlertSelect(1),
+            (Action::SelectFirst, Screen::Alerts) => {
+                AppEvent::AlertSelect(-(self.alerts.selected as isize))
+            }
+            (Action::SelectLast, Screen::Alerts) => AppEvent::AlertSelect(
+                self.alerts.rules.len() as isize - 1 - self.alerts.selected as isize,
+            ),
+            (Action::NewAlert, Screen::Alerts) => AppEvent::OpenPrompt(
+                PromptTarget::NewAlert,
+                format!("{} ", self.watching[self.active_window]),
+            ),
+            (Action::EditAlert, Screen::Alerts) => {
+                let rule = self.alerts.rules.get(self.alerts.selected)?;
+                AppEvent::OpenPrompt(
+                    PromptTarget::EditAlert(self.alerts.selected),
+                    rule.to_string(),
+                )
+            }
+            (Action::DeleteAlert, Screen::Alerts) => AppEvent::AlertDelete,
+            (Action::SnoozeAlert, Screen::Alerts) => AppEvent::AlertSnooze,
+            (Action::ToggleRepeat, Screen::Alerts) => AppEvent::AlertToggleRepeat,
+            _ => return None,
+        };
+        Some(event)
+    }
+
     /// Handles the tick event of the terminal.
     ///
     /// The tick event is where you can update the state of your application with any logic that
     /// needs to be updated at a fixed frame rate. E.g. polling a server, updating an animation.
-    pub fn tick(&mut self) {}
+    pub fn tick(&mut self) {
+        for coin in &self.watching {
+            if !self.indicators.contains_key(coin) {
+                let panel = PanelIndicators::new(&self.settings.indicators);
+                self.indicators.insert(coin.clone(), panel);
+            }
+        }
+        for (coin, panel) in self.indicators.iter_mut() {
+            panel.update(coin);
+        }
+        self.portfolio.record();
+        self.arbitrage
+            .record(self.display_currency.as_deref().unwrap_or("USD"));
 
-    /// Set running to false to quit the application.
-    pub fn quit(&mut self) {
-        self.running = false;
+        if self.toast.as_ref().is_some_and(|t| t.expired()) {
+            self.toast = None;
+            self.dirty = true;
+        }
+        self.record_price_moves();
+        if self.update_banner() {
+            self.dirty = true;
+        }
+
+        let fired = self.alerts.check();
+        if !fired.is_empty() {
+            self.dirty = true;
+            // one-shot rules are done now, that has to survive a restart
+            if fired.iter().any(|f| f.done)
+                && let Err(e) = self.alerts.save()
+            {
+                self.alert_error = Some(e.to_string());
+            }
+            let until = chrono::Local::now().timestamp_millis() + FLASH_MS;
+            for f in fired.iter() {
+                self.flashing.insert(f.product.clone(), until);
+            }
+            // terminal bell, the tui doesnt care about it
+            let mut stdout = std::io::stdout();
+            let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+        }
     }
 
-    /// Calculate next step
-    ///
-    /// this also breaks if we subtract/add too much to the color and it goes out of type bounds
-    /// TODO: add out of type bounds checker
-    fn calc_color(&mut self) {
-        // check bounds
-        if self.color <= LOWER_COLOR_BOUND {
-            self.color_add = true;
+    /// Starts watching the product, the feed gets told to send its messages too
+    fn add_product(&mut self, product: String) {
+        if self.watching.contains(&product) {
+            return;
         }
+        if let Some(feed) = &self.feed {
+            let _ = feed.send(FeedCommand::Subscribe(vec![product.clone()]));
+        }
+        self.price_mult.insert(product.clone(), 0.5);
+        self.watching.push(product);
+        self.active_window = self.watching.len() - 1;
+    }
+
+    /// Switches to the watchlist after the current one, the feed only gets told about the products
+    /// that actually changed
+    fn next_watchlist(&mut self) {
+        // empty watchlists would leave nothing to show, so they get skipped
+        let names = self
+            .settings
+            .watchlists
+            .iter()
+            .filter(|(_, p)| !p.is_empty())
+            .map(|(n, _)| n.clone())
+            .collect::<Vec<String>>();
+        let next = match self
+            .watchlist
+            .as_ref()
+            .and_then(|w| names.iter().position(|n| n == w))
+        {
+            Some(idx) => names.get((idx + 1) % names.len()),
+            None => names.first(),
+        };
+        let Some(name) = next.cloned() else {
+            return;
+        };
+        let products = self.settings.watchlists[&name].clone();
+        self.set_watching(products);
+        self.watchlist = Some(name);
+    }
 
-        if self.color >= UPPER_COLOR_BOUND {
-            self.color_add = false;
+    /// Replaces the watched products, the feed only gets told about the products that actually
+    /// changed
+    fn set_watching(&mut self, products: Vec<String>) {
+        if let Some(feed) = &self.feed {
+            let removed = self
+                .watching
+                .iter()
+                .filter(|p| !products.contains(p) && !self.pinned_products.contains(p))
+                .cloned()
+                .collect::<Vec<String>>();
+            let added = products
+                .iter()
+                .filter(|p| !self.watching.contains(p) && !self.pinned_products.contains(p))
+                .cloned()
+                .collect::<Vec<String>>();
+            if !removed.is_empty() {
+                let _ = feed.send(FeedCommand::Unsubscribe(removed));
+            }
+            if !added.is_empty() {
+                let _ = feed.send(FeedCommand::Subscribe(added));
+            }
+        }
+
+        for p in products.iter() {
+            self.price_mult.entry(p.clone()).or_insert(0.5);
+        }
+        self.watching = products;
+        self.active_window = 0;
+        self.maximized = false;
+        self.overview.selected = 0;
+    }
+
+    /// Applies the changed config file. A broken config only shows an error, everything keeps
+    /// running with the previous one.
+    fn reload_config(&mut self) {
+        let Some(watch) = &self.config_watch else {
+            return;
+        };
+        let loaded = watch.load().and_then(|mut s| {
+            s.watching = self.catalog.validate(&s.watching)?;
+            for products in s.watchlists.values_mut() {
+                *products = self.catalog.validate(products)?;
+            }
+            Ok(s)
+        });
+        let mut new = match loaded {
+            Ok(s) => s,
+            Err(e) => {
+                self.toast = Some(Toast::error(format!(
+                    "Config not reloaded, keeping the previous one\n{e:#}"
+                )));
+                return;
+            }
+        };
+
+        // the event loop and the buffers are already running with these
+        let restart = new.tick_rate_ms != self.settings.tick_rate_ms
+            || new.frame_rate != self.settings.frame_rate
+            || new.messages_per_product != self.settings.messages_per_product;
+        new.tick_rate_ms = self.settings.tick_rate_ms;
+        new.frame_rate = self.settings.frame_rate;
+        new.messages_per_product = self.settings.messages_per_product;
+
+        if new.layout != self.settings.layout {
+            self.window_type = new.layout;
+        }
+        if new.border_animation != self.settings.border_animation {
+            self.border_animation = new.border_animation;
+        }
+        let products = match &self.watchlist {
+            Some(name) => new.watchlists.get(name).cloned(),
+            None => (new.watching != self.settings.watching).then(|| new.watching.clone()),
+        };
+        if let Some(p) = products
+            && p != self.watching
+        {
+            self.set_watching(p);
+        }
+        self.settings = new;
+
+        self.toast = Some(Toast::info(match restart {
+            true => "Config reloaded, the tick rate, frame rate and buffer size need a restart",
+            false => "Config reloaded",
+        }));
+    }
+
+    /// Applies the text of the prompt to whatever it was opened for
+    fn submit_prompt(&mut self, target: PromptTarget, text: &str) {
+        let idx = match target {
+            PromptTarget::NewAlert => None,
+            PromptTarget::EditAlert(i) => Some(i),
+            PromptTarget::Command => return self.run_command(text),
+        };
+        // the product needs to be subscribed, otherwise the rule never gets any prices
+        self.alert_error = AlertRule::parse(text)
+            .and_then(|mut rule| {
+                rule.product = self.catalog.validate(&[rule.product])?.remove(0);
+                self.pin_product(rule.product.clone());
+                self.alerts.upsert(idx, rule)
+            })
+            .err()
+            .map(|e| e.to_string());
+    }
+
+    /// Runs a line of the command line, errors and results show up as a toast
+    fn run_command(&mut self, line: &str) {
+        if line.is_empty() {
+            return;
+        }
+        if let Err(e) = self.commands.record(line) {
+            self.toast = Some(Toast::error(format!("Command history not saved\n{e:#}")));
+        }
+        let line = self.commands.resolve(line, &self.catalog, &self.watching);
+        match line.parse().and_then(|c| self.execute(c)) {
+            Ok(Some(message)) => self.toast = Some(Toast::info(message)),
+            Ok(None) => {}
+            Err(e) => self.toast = Some(Toast::error(format!("{e:#}"))),
         }
+    }
+
+    /// Does what the command says, some commands have a result worth showing
+    fn execute(&mut self, command: Command) -> anyhow::Result<Option<String>> {
+        match command {
+            Command::Action(action) => match self.action_event(action) {
+                Some(event) => self.events.send(event),
+                None => bail!("{action} does nothing here"),
+            },
+            Command::Add(product) => {
+                let product = self.catalog.validate(&[product])?.remove(0);
+                if self.watching.contains(&product) {
+                    bail!("{product} is already watched");
+                }
+                self.add_product(product);
+            }
+            Command::Remove(product) => self.remove_product(&product)?,
+            Command::Timeframe(secs) => self.settings.timeframe_secs = secs,
+            Command::Layout(layout) => {
+                self.window_type = layout;
+                self.maximized = false;
+            }
+            Command::Alert(mut rule) => {
+                rule.product = self.catalog.validate(&[rule.product])?.remove(0);
+                let message = format!("New alert {rule}");
+                self.pin_product(rule.product.clone());
+                self.alerts.upsert(None, rule)?;
+                return Ok(Some(message));
+            }
+            Command::ExportCsv(path) => {
+                let (path, count) = export_csv(&self.watching, path)?;
+                return Ok(Some(format!(
+                    "Exported {count} messages to {}",
+                    path.display()
+                )));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Stops watching the product, it stays subscribed when something else needs its prices
+    fn remove_product(&mut self, product: &str) -> anyhow::Result<()> {
+        let Some(idx) = self.watching.iter().position(|w| w == product) else {
+            bail!("{product} isnt watched");
+        };
+        if self.watching.len() == 1 {
+            bail!("{product} is the last watched product");
+        }
+        if let Some(feed) = &self.feed
+            && !self.pinned_products.iter().any(|p| p == product)
+        {
+            let _ = feed.send(FeedCommand::Unsubscribe(vec![product.to_string()]));
+        }
+        self.watching.remove(idx);
+        if self.active_window > idx || self.active_window >= self.watching.len() {
+            self.active_window -= 1;
+        }
+        self.overview.selected = 0;
+        Ok(())
+    }
 
-        if self.color_add {
-            self.color += CHANGE_COLOR_BY;
+    /// Keeps the product subscribed without a chart, like the products of the alerts from startup
+    fn pin_product(&mut self, product: String) {
+        if self.pinned_products.contains(&product) {
             return;
         }
+        if let Some(feed) = &self.feed
+            && !self.watching.contains(&product)
+        {
+            let _ = feed.send(FeedCommand::Subscribe(vec![product.clone()]));
+        }
+        self.pinned_products.push(product);
+    }
+
+    /// Picks the meme for how the focused coin is doing, true if it changed
+    fn update_banner(&mut self) -> bool {
+        let settings = &self.settings;
+        self.banner.update(
+            &settings.memes,
+            self.watching.get(self.active_window).map(|p| p.as_str()),
+            settings.timeframe_secs,
+            settings.mood_threshold,
+            settings.meme_rotate_secs,
+        )
+    }
+
+    /// Set running to false to quit the application.
+    pub fn quit(&mut self) {
+        self.running = false;
+    }
 
-        self.color -= CHANGE_COLOR_BY;
+    /// Remembers when the price of a watched product moved since the last tick, for the pulse of
+    /// its border
+    fn record_price_moves(&mut self) {
+        let now = chrono::Local::now().timestamp_millis();
+        let messages = ws_messages.lock();
+        for product in self.watching.iter() {
+            let Some(price) = messages
+                .get(product)
+                .and_then(|m| m.back())
+                .and_then(|m| m.price.parse::<f64>().ok())
+            else {
+                continue;
+            };
+            if let Some(last) = self.last_prices.insert(product.clone(), price)
+                && last != price
+            {
+                self.pulses.insert(product.clone(), (now, price > last));
+            }
+        }
     }
 }
@@ -0,0 +1,237 @@
+//! Cross-quote spread monitor
+//!
+//! Groups every product of the feed by its base asset, so `BTC-USD`, `BTC-USDC` and `BTC-EUR` end
+//! up together, converts their prices into one quote currency and tracks how far apart the
+//! cheapest and the most expensive one are.
+
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Axis, Cell, Chart, Dataset, GraphType, LegendPosition, Row, Table},
+};
+use ringbuffer::RingBuffer;
+
+use crate::{
+    currency::{Currency, rate_avoiding},
+    sockets::ws_messages,
+    theme::Theme,
+    utils::convert_timestamp_to_locale,
+};
+
+/// Spreads at or above this many percent get highlighted
+pub const DEFAULT_THRESHOLD: f64 = 0.5;
+/// How many spread values per base asset are kept for the chart
+const MAX_HISTORY: usize = 10000;
+
+/// The converted prices of every product of one base asset
+#[derive(Debug, Clone)]
+pub struct BaseSpread {
+    pub base: String,
+    /// `(product, price in the common quote)`, cheapest first
+    pub prices: Vec<(String, f64)>,
+    /// Difference between the most expensive and the cheapest price, in percent of the cheapest
+    pub spread: f64,
+}
+
+/// Every base asset that trades against more than one quote, with the spread between them
+pub fn spreads(quote: &str) -> Vec<BaseSpread> {
+    let last = ws_messages
+        .lock()
+        .iter()
+        .filter_map(|(product, buf)| {
+            let price = buf.back()?.price.parse::<f64>().ok()?;
+            Some((product.clone(), price))
+        })
+        .collect::<Vec<(String, f64)>>();
+
+    let mut groups: BTreeMap<String, Vec<(String, f64)>> = BTreeMap::new();
+    for (product, price) in last {
+        let Some((base, product_quote)) = product.split_once('-') else {
+            continue;
+        };
+        // products without an independent rate to the common quote cant be compared
+        let Some(r) = rate_avoiding(product_quote, quote, &[base]) else {
+            continue;
+        };
+        groups
+            .entry(base.to_string())
+            .or_default()
+            .push((product.clone(), price * r));
+    }
+
+    groups
+        .into_iter()
+        .filter(|(_, prices)| prices.len() > 1)
+        .map(|(base, mut prices)| {
+            prices.sort_by(|a, b| a.1.total_cmp(&b.1));
+            let lo = prices.first().map(|p| p.1).unwrap_or(0.0);
+            let hi = prices.last().map(|p| p.1).unwrap_or(0.0);
+            let spread = if lo > 0.0 {
+                (hi / lo - 1.0) * 100.0
+            } else {
+                0.0
+            };
+            BaseSpread {
+                base,
+                prices,
+                spread,
+            }
+        })
+        .collect()
+}
+
+/// The spreads over the session
+#[derive(Debug, Clone)]
+pub struct ArbitrageMonitor {
+    /// `(timestamp_ms, spread)` per base asset
+    history: HashMap<String, VecDeque<(f64, f64)>>,
+    /// Spreads at or above this many percent get highlighted
+    pub threshold: f64,
+}
+
+impl Default for ArbitrageMonitor {
+    fn default() -> Self {
+        Self {
+            history: HashMap::new(),
+            threshold: DEFAULT_THRESHOLD,
+        }
+    }
+}
+
+impl ArbitrageMonitor {
+    /// Records the current spreads for the chart
+    pub fn record(&mut self, quote: &str) {
+        let now = chrono::Local::now().timestamp_millis() as f64;
+        for s in spreads(quote) {
+            let h = self.history.entry(s.base).or_default();
+            h.push_back((now, s.spread));
+            if h.len() > MAX_HISTORY {
+                h.pop_front();
+            }
+        }
+    }
+}
+
+pub fn render_arbitrage(
+    frame: &mut Frame,
+    area: Rect,
+    monitor: &ArbitrageMonitor,
+    quote: &str,
+    theme: &Theme,
+) {
+    let rows = spreads(quote);
+    let crc = Currency::lookup(quote);
+    let [table_area, chart_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Fill(1)]).areas(area);
+
+    let body = rows.iter().map(|s| {
+        let color = match s.spread >= monitor.threshold {
+            true => theme.warning,
+            false => theme.label,
+        };
+        let (cheap, rich) = (s.prices.first(), s.prices.last());
+        let fmt = |p: Option<&(String, f64)>| {
+            p.map(|(product, price)| format!("{product} {}", crc.format(*price)))
+                .unwrap_or_default()
+        };
+
+        Row::new(vec![
+            Cell::from(s.base.clone()),
+            Cell::from(Line::from(format!("{}", s.prices.len())).right_aligned()),
+            Cell::from(fmt(cheap)),
+            Cell::from(fmt(rich)),
+            Cell::from(Line::from(format!("{:.3}%", s.spread)).right_aligned()),
+        ])
+        .style(Style::new().fg(color))
+    });
+
+    let table = Table::new(
+        body,
+        [
+            Constraint::Length(8),
+            Constraint::Length(7),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Length(10),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            Cell::from("Base"),
+            Cell::from(Line::from("Quotes").right_aligned()),
+            Cell::from("Cheapest"),
+            Cell::from("Richest"),
+            Cell::from(Line::from("Spread").right_aligned()),
+        ])
+        .style(Style::new().bold().fg(theme.label)),
+    );
+
+    let title = match rows.is_empty() {
+        true => format!("Spreads in {quote} - watch one asset in several quotes"),
+        false => format!("Spreads in {quote} - highlight at {}%", monitor.threshold),
+    };
+    frame.render_widget(theme.panel(table).title(title), table_area);
+
+    let mut bases = monitor.history.keys().cloned().collect::<Vec<String>>();
+    bases.sort();
+    let series = bases
+        .iter()
+        .map(|b| (b, monitor.history[b].iter().copied().collect::<Vec<_>>()))
+        .collect::<Vec<(&String, Vec<(f64, f64)>)>>();
+
+    let now = chrono::Local::now().timestamp_millis() as f64;
+    let start = series
+        .iter()
+        .filter_map(|(_, d)| d.first().map(|p| p.0))
+        .fold(now, f64::min);
+    let end = now.max(start + 1.0);
+    let hi = series
+        .iter()
+        .flat_map(|(_, d)| d.iter().map(|p| p.1))
+        .fold(monitor.threshold, f64::max)
+        * 1.1;
+    let threshold_line = [(start, monitor.threshold), (end, monitor.threshold)];
+
+    let mut datasets = vec![
+        Dataset::default()
+            .style(theme.warning)
+            .marker(theme.marker)
+            .graph_type(GraphType::Line)
+            .data(&threshold_line),
+    ];
+    datasets.extend(series.iter().map(|(b, d)| {
+        Dataset::default()
+            .name(b.to_string())
+            .style(theme.coin_color(b))
+            .marker(theme.marker)
+            .data(d)
+    }));
+
+    let chart = Chart::new(datasets)
+        .legend_position(Some(LegendPosition::TopLeft))
+        .x_axis(
+            Axis::default()
+                .bounds([start, end])
+                .labels([
+                    convert_timestamp_to_locale(start).fg(theme.label),
+                    convert_timestamp_to_locale(end).fg(theme.label),
+                ])
+                .style(theme.axis),
+        )
+        .y_axis(
+            Axis::default()
+                .bounds([0.0, hi])
+                .labels([
+                    "0%".fg(theme.label),
+                    format!("{:.2}%", hi / 2.0).fg(theme.label),
+                    format!("{hi:.2}%").fg(theme.label),
+                ])
+                .style(theme.axis),
+        );
+
+    frame.render_widget(theme.panel(chart).title("Spread over time"), chart_area);
+}
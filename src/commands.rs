@@ -0,0 +1,558 @@
+//! Command line
+//!
+//! `:` opens a prompt that runs every action of normal mode by its config name, like
+//! `toggle_sma`, and the commands that need arguments:
+//!
+//! - `add BTC-USD` / `remove SOL-USDC`
+//! - `timeframe 15m`
+//! - `layout master`
+//! - `alert BTC-USD > 70000`
+//! - `export csv [path]`
+//!
+//! While typing, a palette lists what fuzzy matches the word under the cursor, tab completes it
+//! with the best match and goes through the others when pressed again. A single word that isnt a
+//! command runs the best match, so `sma` is enough for `toggle_sma`. The commands that were run are
+//! kept in `command_history` in the data directory, up and down go through them.
+
+use std::{
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{Context, anyhow, bail};
+use clap::ValueEnum;
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Clear, List, ListItem, ListState},
+};
+use ringbuffer::RingBuffer;
+
+use crate::{
+    alerts::AlertRule,
+    app::WindowType,
+    keymap::{Action, Keymap, Mode},
+    products::{Catalog, fuzzy_score},
+    sockets::ws_messages,
+    theme::Theme,
+    utils::data_dir,
+};
+
+/// How many commands the history keeps
+const MAX_HISTORY: usize = 500;
+/// How many completions tab goes through at most
+const MAX_COMPLETIONS: usize = 50;
+/// How many rows the palette shows at once
+const PALETTE_ROWS: usize = 8;
+
+/// The commands that take arguments: name, arguments and what they do
+const ARG_COMMANDS: [(&str, &str, &str); 6] = [
+    ("add", "<product>", "watch a product"),
+    ("remove", "<product>", "stop watching a product"),
+    (
+        "timeframe",
+        "<duration>",
+        "how far back the charts go, like 15m",
+    ),
+    ("layout", "<layout>", "arrange the charts"),
+    ("alert", "<rule>", "new alert, like BTC-USD > 70000"),
+    (
+        "export",
+        "csv [path]",
+        "write the buffered ticker messages to a file",
+    ),
+];
+
+#[derive(Debug, Clone)]
+pub enum Command {
+    /// An action of normal mode, like the key for it was pressed
+    Action(Action),
+    Add(String),
+    Remove(String),
+    /// Seconds
+    Timeframe(u64),
+    Layout(WindowType),
+    Alert(AlertRule),
+    /// `None` writes into the data directory
+    ExportCsv(Option<PathBuf>),
+}
+
+/// The actions that can be run as a command, opening the command line from itself does nothing
+fn actions() -> impl Iterator<Item = &'static Action> {
+    Mode::Normal
+        .actions()
+        .iter()
+        .filter(|a| **a != Action::CommandLine)
+}
+
+fn layouts() -> Vec<String> {
+    WindowType::value_variants()
+        .iter()
+        .filter_map(|w| w.to_possible_value())
+        .map(|v| v.get_name().to_string())
+        .collect()
+}
+
+/// Parses durations like `30s`, `15m`, `4h` or `1d`, a plain number is seconds
+fn parse_duration(v: &str) -> anyhow::Result<u64> {
+    let (num, mult) = match v.char_indices().last() {
+        Some((i, 's')) => (&v[..i], 1),
+        Some((i, 'm')) => (&v[..i], 60),
+        Some((i, 'h')) => (&v[..i], 60 * 60),
+        Some((i, 'd')) => (&v[..i], 24 * 60 * 60),
+        _ => (v, 1),
+    };
+    let secs = num.parse::<u64>().ok().filter(|n| *n > 0);
+    match secs.and_then(|n| n.checked_mul(mult)) {
+        Some(secs) => Ok(secs),
+        None => bail!("Expected a duration like 30s, 15m or 4h but got '{v}'"),
+    }
+}
+
+impl FromStr for Command {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let line = line.trim();
+        let (name, args) = line
+            .split_once(char::is_whitespace)
+            .map(|(n, a)| (n, a.trim()))
+            .unwrap_or((line, ""));
+        let arg = |what: &str| match args.split_whitespace().collect::<Vec<_>>().as_slice() {
+            [a] => Ok(a.to_string()),
+            [] => Err(anyhow!("{name} needs a {what}")),
+            _ => Err(anyhow!("{name} only takes a {what}")),
+        };
+
+        let command = match name {
+            "add" => Command::Add(arg("product")?.to_uppercase()),
+            "remove" => Command::Remove(arg("product")?.to_uppercase()),
+            "timeframe" => Command::Timeframe(parse_duration(&arg("duration")?)?),
+            "layout" => {
+                let layout = arg("layout")?;
+                Command::Layout(WindowType::from_str(&layout, true).map_err(|_| {
+                    anyhow!(
+                        "Unknown layout '{layout}', expected {}",
+                        layouts().join(" or ")
+                    )
+                })?)
+            }
+            "alert" => Command::Alert(AlertRule::parse(args)?),
+            "export" => match args.split_once(char::is_whitespace) {
+                Some(("csv", path)) => Command::ExportCsv(Some(PathBuf::from(path.trim()))),
+                None if args == "csv" => Command::ExportCsv(None),
+                _ => bail!("export needs a format, only csv is supported"),
+            },
+            _ => {
+                let action = actions()
+                    .find(|a| a.to_string() == name)
+                    .ok_or_else(|| anyhow!("Unknown command '{name}', tab shows them all"))?;
+                if !args.is_empty() {
+                    bail!("{name} doesnt take arguments");
+                }
+                Command::Action(*action)
+            }
+        };
+        Ok(command)
+    }
+}
+
+/// A line the prompt can be completed to
+#[derive(Debug, Clone)]
+pub struct Completion {
+    /// The whole line after completing
+    pub line: String,
+    /// The word that got completed
+    pub word: String,
+    pub usage: &'static str,
+    pub description: &'static str,
+}
+
+/// What the last word of the line can be completed to, best first
+fn completions(line: &str, catalog: &Catalog, watching: &[String]) -> Vec<Completion> {
+    let (head, word) = match line.rsplit_once(char::is_whitespace) {
+        Some((head, word)) => (head.trim_end(), word),
+        None => ("", line),
+    };
+    let mut args = head.split_whitespace();
+    let command = args.next();
+    let idx = args.count();
+
+    // the command name itself
+    let Some(command) = command else {
+        let candidates = ARG_COMMANDS
+            .iter()
+            .map(|(name, usage, description)| (name.to_string(), *usage, *description, true))
+            .chain(actions().map(|a| (a.to_string(), "", a.description(), false)));
+        return rank(word, candidates, |(name, usage, description, args)| {
+            Completion {
+                line: match args {
+                    true => format!("{name} "),
+                    false => name.clone(),
+                },
+                word: name,
+                usage,
+                description,
+            }
+        });
+    };
+
+    let products = match catalog.products.is_empty() {
+        true => watching.to_vec(),
+        false => catalog.products.iter().map(|p| p.id.clone()).collect(),
+    };
+    let (candidates, more) = match (command, idx) {
+        ("add", 0) => (products, false),
+        ("alert", 0) => (products, true),
+        ("remove", 0) => (watching.to_vec(), false),
+        ("layout", 0) => (layouts(), false),
+        ("export", 0) => (vec!["csv".to_string()], true),
+        _ => return vec![],
+    };
+    rank(word, candidates.into_iter(), |c| Completion {
+        line: match more {
+            true => format!("{head} {c} "),
+            false => format!("{head} {c}"),
+        },
+        word: c,
+        usage: "",
+        description: "",
+    })
+}
+
+/// Sorts the candidates by how well they match, an empty word keeps their order
+fn rank<T>(
+    word: &str,
+    candidates: impl Iterator<Item = T>,
+    to_completion: impl Fn(T) -> Completion,
+) -> Vec<Completion> {
+    let mut hits = candidates
+        .filter_map(|c| {
+            let completion = to_completion(c);
+            let score = match word.is_empty() {
+                true => 0,
+                false => fuzzy_score(word, &completion.word)?,
+            };
+            Some((score, completion))
+        })
+        .collect::<Vec<_>>();
+    // stable, so equal scores stay in the order they came in
+    hits.sort_by_key(|h| std::cmp::Reverse(h.0));
+    hits.into_iter()
+        .take(MAX_COMPLETIONS)
+        .map(|(_, c)| c)
+        .collect()
+}
+
+/// History and tab completion of the command line, the text itself is in the prompt
+#[derive(Debug, Clone, Default)]
+pub struct CommandLine {
+    path: PathBuf,
+    history: Vec<String>,
+    /// Where in the history up and down are, `None` while typing
+    browsing: Option<usize>,
+    /// What was typed before going through the history
+    draft: String,
+    /// The completions tab goes through and which one is in the prompt
+    cycle: Option<(Vec<Completion>, usize)>,
+}
+
+impl CommandLine {
+    pub fn default_path() -> PathBuf {
+        data_dir().join("command_history")
+    }
+
+    /// Loads the history, without it the command line still works so errors just mean no history
+    pub fn load(path: &Path) -> Self {
+        let history = std::fs::read_to_string(path)
+            .map(|raw| raw.lines().map(|l| l.to_string()).collect())
+            .unwrap_or_default();
+        Self {
+            path: path.to_path_buf(),
+            history,
+            ..Default::default()
+        }
+    }
+
+    /// Adds the command to the history and writes it to the file
+    pub fn record(&mut self, line: &str) -> anyhow::Result<()> {
+        if self.history.last().is_some_and(|l| l == line) {
+            return Ok(());
+        }
+        self.history.push(line.to_string());
+        let skip = self.history.len().saturating_sub(MAX_HISTORY);
+        self.history.drain(..skip);
+
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, self.history.join("\n") + "\n")
+            .with_context(|| format!("Failed to write {}", self.path.display()))
+    }
+
+    /// Starts from the newest entry and without a completion
+    pub fn reset(&mut self) {
+        self.browsing = None;
+        self.draft.clear();
+        self.cycle = None;
+    }
+
+    pub fn history_up(&mut self, text: &mut String) {
+        let pos = match self.browsing {
+            Some(0) => return,
+            Some(p) => p - 1,
+            None if self.history.is_empty() => return,
+            None => {
+                self.draft = text.clone();
+                self.history.len() - 1
+            }
+        };
+        self.browsing = Some(pos);
+        self.cycle = None;
+        *text = self.history[pos].clone();
+    }
+
+    /// Going past the newest entry brings back what was typed
+    pub fn history_down(&mut self, text: &mut String) {
+        let Some(pos) = self.browsing else {
+            return;
+        };
+        self.cycle = None;
+        match self.history.get(pos + 1) {
+            Some(l) => {
+                self.browsing = Some(pos + 1);
+                *text = l.clone();
+            }
+            None => {
+                self.browsing = None;
+                *text = std::mem::take(&mut self.draft);
+            }
+        }
+    }
+
+    /// Completes the last word with the best match, pressing it again goes to the next match
+    pub fn complete(&mut self, text: &mut String, catalog: &Catalog, watching: &[String]) {
+        if let Some((list, idx)) = self.cycle.as_mut()
+            && list.get(*idx).is_some_and(|c| c.line == *text)
+        {
+            *idx = (*idx + 1) % list.len();
+            *text = list[*idx].line.clone();
+            return;
+        }
+
+        let list = completions(text, catalog, watching);
+        match list.as_slice() {
+            [] => self.cycle = None,
+            [only] => {
+                *text = only.line.clone();
+                self.cycle = None;
+            }
+            [first, ..] => {
+                *text = first.line.clone();
+                self.cycle = Some((list, 0));
+            }
+        }
+    }
+
+    /// The text was changed by typing
+    pub fn typed(&mut self) {
+        self.reset();
+    }
+
+    /// A single word that isnt a command becomes the command that matches it best
+    pub fn resolve(&self, line: &str, catalog: &Catalog, watching: &[String]) -> String {
+        if line.contains(char::is_whitespace) || line.parse::<Command>().is_ok() {
+            return line.to_string();
+        }
+        match completions(line, catalog, watching).first() {
+            Some(c) => c.line.trim_end().to_string(),
+            None => line.to_string(),
+        }
+    }
+
+    /// The completions of what is typed, in a popup at the bottom of `area`
+    #[allow(clippy::too_many_arguments)]
+    pub fn render_palette(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        text: &str,
+        catalog: &Catalog,
+        watching: &[String],
+        keymap: &Keymap,
+        theme: &Theme,
+    ) {
+        let (list, selected) = match &self.cycle {
+            Some((list, idx)) => (list.clone(), Some(*idx)),
+            None => (completions(text, catalog, watching), None),
+        };
+        if list.is_empty() {
+            return;
+        }
+
+        let word_width = list.iter().map(|c| c.word.len()).max().unwrap_or(0);
+        let usage_width = list.iter().map(|c| c.usage.len()).max().unwrap_or(0);
+        let items = list
+            .iter()
+            .map(|c| {
+                ListItem::new(Line::from(vec![
+                    format!("{:<word_width$} ", c.word).fg(theme.info),
+                    format!("{:<usage_width$} ", c.usage).fg(theme.muted),
+                    c.description.fg(theme.label),
+                ]))
+            })
+            .collect::<Vec<_>>();
+        let content = items.iter().map(|i| i.width()).max().unwrap_or(0) as u16;
+
+        let width = (content + 4).min(area.width);
+        let height = (list.len().min(PALETTE_ROWS) as u16 + 2).min(area.height);
+        let area = Rect::new(area.x, area.bottom() - height, width, height);
+        let mut state = ListState::default().with_selected(selected);
+        frame.render_widget(Clear, area);
+        frame.render_widget(theme.background_block(), area);
+        frame.render_stateful_widget(
+            theme
+                .panel(List::new(items).highlight_style(Style::new().reversed()))
+                .title(
+                    match keymap.hint(Mode::Command, &[(Action::Complete, "completes")]) {
+                        h if h.is_empty() => format!("{} matches", list.len()),
+                        h => format!("{} matches - {h}", list.len()),
+                    },
+                ),
+            area,
+            &mut state,
+        );
+    }
+}
+
+/// Writes the buffered ticker messages of the products into a csv file, returns where it went and
+/// how many messages got written
+pub fn export_csv(products: &[String], path: Option<PathBuf>) -> anyhow::Result<(PathBuf, usize)> {
+    let path = path.unwrap_or_else(|| {
+        let now = chrono::Local::now().format("%Y%m%d-%H%M%S");
+        data_dir().join("exports").join(format!("ticker-{now}.csv"))
+    });
+    if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let mut writer = csv::Writer::from_path(&path)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    let mut count = 0;
+    let messages = ws_messages.lock();
+    for product in products {
+        for m in messages.get(product).into_iter().flat_map(|m| m.iter()) {
+            writer.serialize(m)?;
+            count += 1;
+        }
+    }
+    writer.flush()?;
+    Ok((path, count))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::products::ProductInfo;
+
+    fn catalog(ids: &[&str]) -> Catalog {
+        Catalog {
+            products: ids
+                .iter()
+                .map(|id| {
+                    let (base, quote) = id.split_once('-').unwrap();
+                    ProductInfo {
+                        id: id.to_string(),
+                        base_currency: base.into(),
+                        quote_currency: quote.into(),
+                        display_name: id.replace('-', "/"),
+                        status: "online".into(),
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    fn lines(line: &str, catalog: &Catalog, watching: &[String]) -> Vec<String> {
+        completions(line, catalog, watching)
+            .into_iter()
+            .map(|c| c.line)
+            .collect()
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("15m").unwrap(), 900);
+        assert_eq!(parse_duration("30s").unwrap(), 30);
+        assert_eq!(parse_duration("4h").unwrap(), 4 * 60 * 60);
+        assert_eq!(parse_duration("1d").unwrap(), 24 * 60 * 60);
+        assert_eq!(parse_duration("90").unwrap(), 90);
+
+        let too_long = format!("{}d", u64::MAX);
+        for v in ["0", "0m", "", "m", "-5m", "1.5h", "5w", too_long.as_str()] {
+            assert!(parse_duration(v).is_err(), "{v}");
+        }
+    }
+
+    #[test]
+    fn parses_commands() {
+        assert!(matches!("add btc-usd".parse(), Ok(Command::Add(p)) if p == "BTC-USD"));
+        assert!(matches!(
+            "timeframe 15m".parse(),
+            Ok(Command::Timeframe(900))
+        ));
+        assert!(matches!("help".parse(), Ok(Command::Action(Action::Help))));
+        assert!(matches!(
+            "alert BTC-USD > 70000".parse(),
+            Ok(Command::Alert(r)) if r.condition == crate::alerts::Condition::Above { price: 70000.0 }
+        ));
+
+        for v in [
+            "add",
+            "add BTC-USD ETH-USD",
+            "timeframe 0",
+            "help me",
+            "nope",
+        ] {
+            assert!(v.parse::<Command>().is_err(), "{v}");
+        }
+    }
+
+    #[test]
+    fn parses_export() {
+        assert!(matches!("export csv".parse(), Ok(Command::ExportCsv(None))));
+        assert!(matches!(
+            "export  csv  /tmp/ticks.csv ".parse(),
+            Ok(Command::ExportCsv(Some(p))) if p == Path::new("/tmp/ticks.csv")
+        ));
+        for v in [
+            "export",
+            "export json",
+            "export csvx",
+            "export json out.json",
+        ] {
+            assert!(v.parse::<Command>().is_err(), "{v}");
+        }
+    }
+
+    #[test]
+    fn completes_the_second_word() {
+        let catalog = catalog(&["BTC-USD", "ETH-USD", "SOL-USDC"]);
+        let watching = vec!["SOL-USDC".to_string()];
+
+        assert_eq!(lines("add bt", &catalog, &watching), ["add BTC-USD"]);
+        // alert rules go on after the product
+        assert_eq!(lines("alert eth", &catalog, &watching), ["alert ETH-USD "]);
+        assert_eq!(lines("add ", &catalog, &watching).len(), 3);
+        // without a catalog the watched products are all there is
+        assert_eq!(
+            lines("add ", &Catalog::default(), &watching),
+            ["add SOL-USDC"]
+        );
+        assert_eq!(lines("remove ", &catalog, &watching), ["remove SOL-USDC"]);
+        assert!(lines("add BTC-USD more", &catalog, &watching).is_empty());
+    }
+}
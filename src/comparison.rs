@@ -0,0 +1,94 @@
+//! Comparison chart
+//!
+//! Plots multiple products in a single chart. Every line is normalized to the percent change since
+//! the start of the visible window, so coins with completely different prices can be compared.
+
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::Stylize,
+    widgets::{Axis, Chart, Dataset, LegendPosition},
+};
+
+use crate::{sockets::price_history, theme::Theme, utils::convert_timestamp_to_locale};
+
+/// Converts the price points into the percent change relative to the first point that is inside
+/// the window
+pub fn normalize(points: &[(f64, f64)], start: f64) -> Vec<(f64, f64)> {
+    let visible = points.iter().filter(|(t, _)| *t >= start);
+    let Some(base) = visible.clone().map(|(_, p)| *p).find(|p| *p != 0.0) else {
+        return vec![];
+    };
+
+    visible
+        .map(|(t, p)| (*t, (p / base - 1.0) * 100.0))
+        .collect()
+}
+
+/// Renders all given products into a single chart with a legend
+pub fn render_comparison(
+    frame: &mut Frame,
+    area: Rect,
+    products: &[String],
+    t_changee: f64,
+    theme: &Theme,
+) {
+    let now = chrono::Local::now().timestamp_millis() as f64;
+    let start = now - t_changee * 5.0;
+
+    let series = products
+        .iter()
+        .map(|p| (p, normalize(&price_history(p), start)))
+        .collect::<Vec<(&String, Vec<(f64, f64)>)>>();
+
+    // keep the axis symmetric so that the middle label is always the 0% line
+    let max = series
+        .iter()
+        .flat_map(|(_, d)| d.iter().map(|(_, v)| v.abs()))
+        .fold(0.0f64, f64::max);
+
+    // give the lines some room so they dont stick to the border
+    let hi = (max * 1.1).max(0.05);
+    let lo = -hi;
+
+    let datasets = series
+        .iter()
+        .map(|(product, data)| {
+            let last = data.last().map(|(_, v)| *v).unwrap_or(0.0);
+            Dataset::default()
+                .name(format!("{product} {last:+.2}%"))
+                .style(theme.coin_color(product))
+                .marker(theme.marker)
+                .data(data)
+        })
+        .collect::<Vec<Dataset>>();
+
+    //                                                                  TIME AXIS
+    let x_axis = Axis::default()
+        .style(theme.axis)
+        .bounds([start, now + t_changee])
+        .labels([
+            convert_timestamp_to_locale(start).fg(theme.label),
+            convert_timestamp_to_locale(now + t_changee).fg(theme.label),
+        ]);
+
+    //                                                                  PERCENT AXIS
+    let y_axis = Axis::default()
+        .bounds([lo, hi])
+        .labels([
+            format!("{lo:+.2}%").fg(theme.down),
+            "0.00%".fg(theme.label),
+            format!("{hi:+.2}%").fg(theme.up),
+        ])
+        .style(theme.axis);
+
+    let chart = Chart::new(datasets)
+        .x_axis(x_axis)
+        .y_axis(y_axis)
+        .legend_position(Some(LegendPosition::TopLeft))
+        .hidden_legend_constraints((Constraint::Percentage(50), Constraint::Percentage(50)));
+
+    let widget = theme.panel(chart).title("Comparison");
+
+    frame.render_widget(widget, area);
+}
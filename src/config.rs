@@ -0,0 +1,773 @@
+//! Config file
+//!
+//! `config.toml` in the config directory. Every key is optional, flags on the command line win over
+//! the values in here. Changes get picked up while running, only the tick rate, frame rate and
+//! buffer size need a restart. A broken file is ignored until it is fixed.
+//!
+//! ```toml
+//! # the products that are watched when neither --watching nor --watchlist is given
+//! watching = ["BTC-USD", "ETH-USD"]
+//! # dark, light, high-contrast or an own one, see theme.rs
+//! theme = "dark"
+//!
+//! # named sets of products, --watchlist <name> or `w` to switch between them
+//! [watchlists]
+//! majors = ["BTC-USD", "ETH-USD", "SOL-USD"]
+//! memes = ["DOGE-USD", "SHIB-USD", "PEPE-USD"]
+//!
+//! [buffer]
+//! # how many ticker messages are kept per product, defaults to 10000 per watched product
+//! messages_per_product = 50000
+//!
+//! [timing]
+//! # how often the indicators, alerts etc. get updated
+//! tick_rate_ms = 500
+//! # how often the screen gets redrawn at most, frames without changes are skipped
+//! frame_rate = 10
+//!
+//! [chart]
+//! # how far the charts look back, they look a fifth of it ahead
+//! timeframe_secs = 300
+//! # "splace" or "master"
+//! layout = "master"
+//! # the window that the buy/ sell pressure gets calculated over
+//! pressure_window_secs = 60
+//! # gradients turning around the chart borders and flashing when the price moves, without
+//! # other changes the turning only gets a few frames per second
+//! border_animation = true
+//!
+//! # what new chart panels show, `1` to `4` toggle them and `<` `>` change the period of the
+//! # selected one, `6` selects the next
+//! [chart.indicators]
+//! # sma, ema, vwap and bollinger
+//! show = ["sma", "bollinger"]
+//! sma_period = 20
+//! ema_period = 12
+//! bollinger_period = 20
+//! bollinger_k = 2.0
+//!
+//! # the header and footer texts, see memes.rs
+//! [memes]
+//! # the bundled stonks pack or own ones, their lines get mixed
+//! packs = ["stonks"]
+//! # how often a new line gets picked
+//! rotate_secs = 60
+//! # from how many percent change of the focused coin over the timeframe the moon or pain lines
+//! # are shown
+//! mood_threshold = 2.0
+//!
+//! # what the terminal can show, see terminal.rs
+//! [display]
+//! # auto, truecolor, 256, 16 or none
+//! colors = "auto"
+//! # rounded, plain, thick, double or ascii
+//! borders = "rounded"
+//! # braille, half-block, dot, block or bar
+//! marker = "braille"
+//!
+//! # on top of the theme
+//! [colors]
+//! buy = "#00ff64"
+//! sell = "#ff0064"
+//! # border gradients per base asset, see GradientColors for everything that works here
+//! coins.BTC = "#f7931a"
+//! coins.SOL = ["#9a45fe", "#5798cb", "#15f096", "#5798cb"]
+//! coins.DOGE = "sunset"
+//!
+//! # action = key or [keys] on top of a preset, see keymap.rs for the actions and modes
+//! [keys]
+//! preset = "default"
+//! quit = ["q", "esc"]
+//! focus_next = ["tab", "n"]
+//! inc_mult_fine = "shift-up"
+//! heatmap = "g h"
+//! cursor.select_first = ["g g", "home"]
+//! command.cancel = ["esc", "ctrl-g"]
+//! ```
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+    str::FromStr,
+    time::Duration,
+};
+
+use anyhow::{Context, bail};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use ratatui::style::Color;
+use serde::{Deserialize, Deserializer, de::Error};
+use tokio::sync::mpsc;
+
+use crate::{
+    app::WindowType,
+    events::{AppEvent, Event},
+    gradient_widget::{Blend, GradientConfig, PRESETS},
+    indicators::{IndicatorDefaults, IndicatorName},
+    keymap::{Action, Keymap, Keys},
+    memes::{DEFAULT_MOOD_THRESHOLD, DEFAULT_PACK, DEFAULT_ROTATE_SECS, Memes},
+    opts::CliOpts,
+    pressure::DEFAULT_PRESSURE_WINDOW,
+    terminal::{BorderStyle, ChartMarker, ColorSupport},
+    theme::{DEFAULT_THEME, Theme},
+    utils::config_dir,
+};
+
+/// Used when the watch list is empty everywhere
+const DEFAULT_PRODUCT: &str = "SOL-USDC";
+pub const DEFAULT_MESSAGES_PER_PRODUCT: usize = 10000;
+pub const DEFAULT_TICK_RATE_MS: u64 = 500;
+pub const DEFAULT_FRAME_RATE: u64 = 10;
+pub const DEFAULT_TIMEFRAME_SECS: u64 = 300;
+/// Editors write a file in several steps, the config gets reloaded once it was quiet for this long
+const RELOAD_DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// A color written as `#rrggbb`, `#rgb`, any other css color like `hsl(30, 90%, 50%)` or by
+/// name like `red`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfigColor(pub Color);
+
+impl FromStr for ConfigColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        // the terminal names first, `red` should stay the red of the terminal
+        if let Ok(c) = Color::from_str(s) {
+            return Ok(ConfigColor(c));
+        }
+        let [r, g, b, _] = colorgrad::Color::from_html(s)
+            .map_err(|_| format!("invalid color '{s}', expected #rrggbb or a name"))?
+            .to_rgba8();
+        Ok(ConfigColor(Color::Rgb(r, g, b)))
+    }
+}
+
+impl<'de> Deserialize<'de> for ConfigColor {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(D::Error::custom)
+    }
+}
+
+/// A border gradient, checked while parsing so mistakes point at their key
+///
+/// ```toml
+/// # one color
+/// BTC = "#f7931a"
+/// # 2 colors or the 4 corners starting top left, any other number flows around the border
+/// SOL = ["#9a45fe", "#5798cb", "#15f096", "#5798cb"]
+/// # the same as a single string
+/// ETH = "#48cbd9, #1e3a8a, #8a2be2"
+/// # a preset, see gradient_widget.rs
+/// DOGE = "sunset"
+/// # blend is rgb or oklab, which keeps the middle as bright as the ends
+/// ADA = { colors = ["#005451", "#1cbfbf"], blend = "oklab" }
+/// XRP = { preset = "viridis", blend = "oklab" }
+/// ```
+#[derive(Debug, Clone)]
+pub struct GradientColors(pub GradientConfig);
+
+impl GradientColors {
+    fn from_colors(colors: &[String], blend: Blend) -> Result<GradientConfig, String> {
+        let c = colors
+            .iter()
+            .map(|c| c.parse::<ConfigColor>().map(|c| c.0))
+            .collect::<Result<Vec<Color>, String>>()?;
+        Ok(match c.as_slice() {
+            [] => return Err("a gradient needs at least one color".to_string()),
+            [a] => GradientConfig::new_1(*a),
+            [a, b] => GradientConfig::new_2(*a, *b),
+            [a, b, c, d] => GradientConfig::new_4(*a, *b, *c, *d),
+            stops => GradientConfig::new_stops(stops),
+        }
+        .blend(blend))
+    }
+
+    fn from_preset(name: &str, blend: Option<Blend>) -> Result<GradientConfig, String> {
+        let g = GradientConfig::preset(name).ok_or_else(|| {
+            format!(
+                "'{name}' is neither a color nor a gradient preset, presets are {}",
+                PRESETS.join(", ")
+            )
+        })?;
+        Ok(match blend {
+            Some(b) => g.blend(b),
+            None => g,
+        })
+    }
+}
+
+impl<'de> Deserialize<'de> for GradientColors {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(deny_unknown_fields)]
+        struct Table {
+            colors: Option<Vec<String>>,
+            preset: Option<String>,
+            blend: Option<String>,
+        }
+
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            One(String),
+            Many(Vec<String>),
+            Table(Table),
+        }
+
+        let gradient = match Raw::deserialize(deserializer)? {
+            Raw::One(s) if split_colors(&s).len() > 1 => {
+                GradientColors::from_colors(&split_colors(&s), Blend::Rgb)
+            }
+            Raw::One(s) => match s.parse::<ConfigColor>() {
+                Ok(c) => Ok(GradientConfig::new_1(c.0)),
+                Err(e) if s.trim_start().starts_with('#') => Err(e),
+                Err(_) => GradientColors::from_preset(&s, None),
+            },
+            Raw::Many(colors) => GradientColors::from_colors(&colors, Blend::Rgb),
+            Raw::Table(t) => {
+                let blend = t.blend.map(|b| b.parse::<Blend>()).transpose();
+                blend.and_then(|blend| match (t.colors, t.preset) {
+                    (Some(colors), None) => {
+                        GradientColors::from_colors(&colors, blend.unwrap_or_default())
+                    }
+                    (None, Some(preset)) => GradientColors::from_preset(&preset, blend),
+                    _ => Err("a gradient needs either colors or a preset".to_string()),
+                })
+            }
+        };
+        gradient.map(GradientColors).map_err(D::Error::custom)
+    }
+}
+
+/// Splits `#f00, rgb(0, 255, 0)` on the commas between the colors
+fn split_colors(s: &str) -> Vec<String> {
+    let mut colors = vec![String::new()];
+    let mut depth = 0;
+    for c in s.chars() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                colors.push(String::new());
+                continue;
+            }
+            _ => {}
+        }
+        colors.last_mut().unwrap().push(c);
+    }
+    colors
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BufferConfig {
+    pub messages_per_product: Option<usize>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TimingConfig {
+    pub tick_rate_ms: Option<u64>,
+    pub frame_rate: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ChartConfig {
+    pub timeframe_secs: Option<u64>,
+    pub layout: Option<WindowType>,
+    pub pressure_window_secs: Option<u64>,
+    pub border_animation: Option<bool>,
+    #[serde(default)]
+    pub indicators: IndicatorsConfig,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct IndicatorsConfig {
+    pub show: Option<Vec<IndicatorName>>,
+    pub sma_period: Option<usize>,
+    pub ema_period: Option<usize>,
+    pub bollinger_period: Option<usize>,
+    pub bollinger_k: Option<f64>,
+}
+
+impl IndicatorsConfig {
+    /// The defaults of the indicators module for everything that isn't set
+    pub fn defaults(&self) -> IndicatorDefaults {
+        let d = IndicatorDefaults::default();
+        IndicatorDefaults {
+            shown: self.show.clone().unwrap_or(d.shown),
+            sma_period: self.sma_period.unwrap_or(d.sma_period),
+            ema_period: self.ema_period.unwrap_or(d.ema_period),
+            bollinger_period: self.bollinger_period.unwrap_or(d.bollinger_period),
+            bollinger_k: self.bollinger_k.unwrap_or(d.bollinger_k),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MemesConfig {
+    pub packs: Option<Vec<String>>,
+    pub rotate_secs: Option<u64>,
+    pub mood_threshold: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DisplayConfig {
+    pub colors: Option<ColorSupport>,
+    pub borders: Option<BorderStyle>,
+    pub marker: Option<ChartMarker>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ColorConfig {
+    pub buy: Option<ConfigColor>,
+    pub sell: Option<ConfigColor>,
+    #[serde(default)]
+    pub coins: HashMap<String, GradientColors>,
+}
+
+impl ColorConfig {
+    /// The colors of the config win over the theme
+    fn apply(&self, mut theme: Theme) -> Theme {
+        if let Some(c) = self.buy {
+            theme.up = c.0;
+        }
+        if let Some(c) = self.sell {
+            theme.down = c.0;
+        }
+        theme.coins.extend(
+            self.coins
+                .iter()
+                .map(|(coin, c)| (coin.to_uppercase(), c.0.clone())),
+        );
+        theme
+    }
+}
+
+/// The `keys` table, the actions right in it are the ones of normal mode
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct KeysConfig {
+    pub preset: Option<String>,
+    #[serde(default)]
+    pub cursor: HashMap<Action, Keys>,
+    #[serde(default)]
+    pub command: HashMap<Action, Keys>,
+    #[serde(flatten)]
+    pub normal: HashMap<Action, Keys>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Config {
+    #[serde(default)]
+    pub watching: Vec<String>,
+    pub theme: Option<String>,
+    /// Named sets of products, switchable while running
+    #[serde(default)]
+    pub watchlists: BTreeMap<String, Vec<String>>,
+    #[serde(default)]
+    pub buffer: BufferConfig,
+    #[serde(default)]
+    pub timing: TimingConfig,
+    #[serde(default)]
+    pub chart: ChartConfig,
+    #[serde(default)]
+    pub memes: MemesConfig,
+    #[serde(default)]
+    pub display: DisplayConfig,
+    #[serde(default)]
+    pub colors: ColorConfig,
+    #[serde(default)]
+    pub keys: KeysConfig,
+}
+
+impl Config {
+    pub fn default_path() -> PathBuf {
+        config_dir().join("config.toml")
+    }
+
+    /// Loads the config, a missing file just means everything is default
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&raw).with_context(|| format!("Invalid config in {}", path.display()))
+    }
+
+    /// Parses and validates the config, the errors name the key that is wrong
+    pub fn parse(raw: &str) -> anyhow::Result<Self> {
+        let config: Self = toml::from_str(raw)?;
+
+        let positive = [
+            (
+                "buffer.messages_per_product",
+                config.buffer.messages_per_product.map(|v| v as u64),
+            ),
+            ("timing.tick_rate_ms", config.timing.tick_rate_ms),
+            ("timing.frame_rate", config.timing.frame_rate),
+            ("chart.timeframe_secs", config.chart.timeframe_secs),
+            (
+                "chart.pressure_window_secs",
+                config.chart.pressure_window_secs,
+            ),
+            ("memes.rotate_secs", config.memes.rotate_secs),
+        ];
+        for (key, v) in positive {
+            if v == Some(0) {
+                bail!("{key} has to be greater than 0");
+            }
+        }
+        let periods = [
+            ("sma_period", config.chart.indicators.sma_period),
+            ("ema_period", config.chart.indicators.ema_period),
+            ("bollinger_period", config.chart.indicators.bollinger_period),
+        ];
+        for (key, v) in periods {
+            if v.is_some_and(|p| p < 2) {
+                bail!("chart.indicators.{key} has to be at least 2");
+            }
+        }
+        if config
+            .chart
+            .indicators
+            .bollinger_k
+            .is_some_and(|k| !(k > 0.0 && k.is_finite()))
+        {
+            bail!("chart.indicators.bollinger_k has to be a number greater than 0");
+        }
+        if config
+            .memes
+            .mood_threshold
+            .is_some_and(|t| !(t > 0.0 && t.is_finite()))
+        {
+            bail!("memes.mood_threshold has to be a number greater than 0");
+        }
+        for (name, products) in config.watchlists.iter() {
+            if products.is_empty() {
+                bail!("watchlists.{name} is empty");
+            }
+        }
+        Keymap::with_overrides(&config.keys)?;
+
+        Ok(config)
+    }
+
+    /// The products of the named watchlist
+    pub fn watchlist(&self, name: &str) -> anyhow::Result<Vec<String>> {
+        match self.watchlists.get(name) {
+            Some(products) => Ok(products.clone()),
+            None if self.watchlists.is_empty() => {
+                bail!("Unknown watchlist '{name}', there are no watchlists in the config")
+            }
+            None => bail!(
+                "Unknown watchlist '{name}', available are: {}",
+                self.watchlists
+                    .keys()
+                    .cloned()
+                    .collect::<Vec<String>>()
+                    .join(", ")
+            ),
+        }
+    }
+}
+
+/// The config merged with the command line, this is what the app actually runs with
+#[derive(Debug, Clone)]
+pub struct Settings {
+    pub watching: Vec<String>,
+    pub watchlist: Option<String>,
+    pub watchlists: BTreeMap<String, Vec<String>>,
+    pub messages_per_product: usize,
+    pub tick_rate_ms: u64,
+    pub frame_rate: u64,
+    pub timeframe_secs: u64,
+    pub layout: WindowType,
+    pub pressure_window: u64,
+    pub border_animation: bool,
+    /// What new chart panels show
+    pub indicators: IndicatorDefaults,
+    /// What the terminal can show, never `Auto`
+    pub colors: ColorSupport,
+    pub borders: BorderStyle,
+    /// The lines of every meme pack
+    pub memes: Memes,
+    pub meme_rotate_secs: u64,
+    pub mood_threshold: f64,
+    /// The theme with the colors of the config on top
+    pub theme: Theme,
+    pub keymap: Keymap,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            watching: vec![DEFAULT_PRODUCT.to_string()],
+            watchlist: None,
+            watchlists: BTreeMap::new(),
+            messages_per_product: DEFAULT_MESSAGES_PER_PRODUCT,
+            tick_rate_ms: DEFAULT_TICK_RATE_MS,
+            frame_rate: DEFAULT_FRAME_RATE,
+            timeframe_secs: DEFAULT_TIMEFRAME_SECS,
+            layout: WindowType::default(),
+            pressure_window: DEFAULT_PRESSURE_WINDOW,
+            border_animation: true,
+            indicators: IndicatorDefaults::default(),
+            colors: ColorSupport::Auto.resolve(),
+            borders: BorderStyle::default(),
+            memes: Memes::load(&[DEFAULT_PACK.to_string()]).unwrap_or_default(),
+            meme_rotate_secs: DEFAULT_ROTATE_SECS,
+            mood_threshold: DEFAULT_MOOD_THRESHOLD,
+            theme: Theme::default(),
+            keymap: Keymap::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Time in ms the charts look ahead, they look 5 times as far back
+    pub fn t_change(&self) -> f64 {
+        self.timeframe_secs as f64 * 1000.0 / 5.0
+    }
+
+    /// Flags that were given win over the file, the file wins over the defaults
+    pub fn resolve(config: &Config, cli: &CliOpts) -> anyhow::Result<Self> {
+        let watching = match (&cli.watching, &cli.watchlist) {
+            (Some(w), _) => w.clone(),
+            (None, Some(name)) => config.watchlist(name)?,
+            (None, None) if !config.watching.is_empty() => config.watching.clone(),
+            (None, None) => vec![DEFAULT_PRODUCT.to_string()],
+        };
+
+        let borders = cli.borders.or(config.display.borders).unwrap_or_default();
+        let mut theme = config.colors.apply(Theme::load(
+            cli.theme
+                .as_deref()
+                .or(config.theme.as_deref())
+                .unwrap_or(DEFAULT_THEME),
+        )?);
+        theme.borders = borders.symbols();
+        theme.marker = config.display.marker.unwrap_or_default().into();
+
+        Ok(Self {
+            messages_per_product: cli
+                .buffer_size
+                .or(config.buffer.messages_per_product)
+                .unwrap_or(DEFAULT_MESSAGES_PER_PRODUCT * watching.len().max(1)),
+            watching,
+            watchlist: cli.watchlist.clone(),
+            watchlists: config.watchlists.clone(),
+            tick_rate_ms: cli
+                .tick_rate
+                .or(config.timing.tick_rate_ms)
+                .unwrap_or(DEFAULT_TICK_RATE_MS),
+            frame_rate: cli
+                .frame_rate
+                .or(config.timing.frame_rate)
+                .unwrap_or(DEFAULT_FRAME_RATE),
+            timeframe_secs: cli
+                .timeframe
+                .or(config.chart.timeframe_secs)
+                .unwrap_or(DEFAULT_TIMEFRAME_SECS),
+            layout: cli.layout.or(config.chart.layout).unwrap_or_default(),
+            pressure_window: cli
+                .pressure_window
+                .or(config.chart.pressure_window_secs)
+                .unwrap_or(DEFAULT_PRESSURE_WINDOW),
+            border_animation: !cli.no_animation && config.chart.border_animation.unwrap_or(true),
+            indicators: config.chart.indicators.defaults(),
+            colors: cli
+                .colors
+                .or(config.display.colors)
+                .unwrap_or_default()
+                .resolve(),
+            borders,
+            memes: Memes::load(
+                config
+                    .memes
+                    .packs
+                    .as_deref()
+                    .unwrap_or(&[DEFAULT_PACK.to_string()]),
+            )?,
+            meme_rotate_secs: config.memes.rotate_secs.unwrap_or(DEFAULT_ROTATE_SECS),
+            mood_threshold: config
+                .memes
+                .mood_threshold
+                .unwrap_or(DEFAULT_MOOD_THRESHOLD),
+            theme,
+            keymap: Keymap::with_overrides(&config.keys)?,
+        })
+    }
+}
+
+/// Watches the config file while the app runs and sends [`AppEvent::ReloadConfig`] when it changed
+#[derive(Debug)]
+pub struct ConfigWatch {
+    pub path: PathBuf,
+    /// The flags still win over the file after a reload
+    pub cli: CliOpts,
+    /// Stops watching when dropped
+    _watcher: RecommendedWatcher,
+}
+
+impl ConfigWatch {
+    pub fn new(
+        path: PathBuf,
+        cli: CliOpts,
+        events: mpsc::UnboundedSender<Event>,
+    ) -> anyhow::Result<Self> {
+        // the directory is watched, editors often replace the file instead of writing into it
+        let dir = path
+            .parent()
+            .filter(|d| !d.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+            .to_path_buf();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+
+        let name = path.file_name().map(|n| n.to_os_string());
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut watcher =
+            notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+                if let Ok(e) = res
+                    && matches!(
+                        e.kind,
+                        EventKind::Create(_) | EventKind::Modify(_) | EventKind::Remove(_)
+                    )
+                    && e.paths.iter().any(|p| p.file_name() == name.as_deref())
+                {
+                    let _ = tx.send(());
+                }
+            })?;
+        watcher
+            .watch(&dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", dir.display()))?;
+
+        tokio::spawn(async move {
+            while rx.recv().await.is_some() {
+                while let Ok(Some(())) = tokio::time::timeout(RELOAD_DEBOUNCE, rx.recv()).await {}
+                if events.send(Event::App(AppEvent::ReloadConfig)).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Ok(Self {
+            path,
+            cli,
+            _watcher: watcher,
+        })
+    }
+
+    /// Loads the config again, merged with the flags like at startup
+    pub fn load(&self) -> anyhow::Result<Settings> {
+        Settings::resolve(&Config::load(&self.path)?, &self.cli)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn error(raw: &str) -> String {
+        format!("{:#}", Config::parse(raw).unwrap_err())
+    }
+
+    #[test]
+    fn parses_a_full_config() {
+        let config = Config::parse(
+            r##"
+            watching = ["BTC-USD"]
+
+            [watchlists]
+            majors = ["BTC-USD", "ETH-USD"]
+
+            [timing]
+            tick_rate_ms = 250
+
+            [chart]
+            layout = "master"
+
+            [chart.indicators]
+            show = ["sma", "bollinger"]
+            ema_period = 30
+
+            [keys]
+            preset = "vim"
+            quit = ["q", "ctrl-c"]
+            "##,
+        )
+        .unwrap();
+        assert_eq!(config.watchlist("majors").unwrap().len(), 2);
+        assert_eq!(config.timing.tick_rate_ms, Some(250));
+        assert_eq!(config.chart.indicators.defaults().ema_period, 30);
+    }
+
+    #[test]
+    fn rejects_zero() {
+        for (raw, key) in [
+            (
+                "[buffer]\nmessages_per_product = 0",
+                "buffer.messages_per_product",
+            ),
+            ("[timing]\ntick_rate_ms = 0", "timing.tick_rate_ms"),
+            ("[timing]\nframe_rate = 0", "timing.frame_rate"),
+            ("[chart]\ntimeframe_secs = 0", "chart.timeframe_secs"),
+            (
+                "[chart]\npressure_window_secs = 0",
+                "chart.pressure_window_secs",
+            ),
+            ("[memes]\nrotate_secs = 0", "memes.rotate_secs"),
+        ] {
+            assert_eq!(error(raw), format!("{key} has to be greater than 0"));
+        }
+    }
+
+    #[test]
+    fn rejects_mood_thresholds_that_never_or_always_match() {
+        for v in ["0.0", "-1.0", "nan", "inf"] {
+            assert_eq!(
+                error(&format!("[memes]\nmood_threshold = {v}")),
+                "memes.mood_threshold has to be a number greater than 0"
+            );
+        }
+        assert!(Config::parse("[memes]\nmood_threshold = 0.5").is_ok());
+    }
+
+    #[test]
+    fn rejects_unknown_keys() {
+        assert!(error("[timing]\ntick_rate = 100").contains("unknown field `tick_rate`"));
+        assert!(error("[chart.indicators]\nsma = 20").contains("unknown field `sma`"));
+        assert!(error("colours = 1").contains("unknown field `colours`"));
+    }
+
+    #[test]
+    fn rejects_empty_watchlists() {
+        assert_eq!(
+            error("[watchlists]\nmajors = [\"BTC-USD\"]\nempty = []"),
+            "watchlists.empty is empty"
+        );
+    }
+
+    #[test]
+    fn names_the_key_of_a_keymap_conflict() {
+        assert!(error("[keys]\nquit = \"?\"").starts_with("keys.quit: '?' is already bound to"));
+        assert!(
+            error("[keys.cursor]\nselect_first = \"j\"")
+                .starts_with("keys.cursor.select_first: 'j' is already bound to")
+        );
+        assert!(
+            error("[keys.command]\nquit = \"q\"")
+                .starts_with("keys.command.quit: quit does nothing in command mode")
+        );
+    }
+}
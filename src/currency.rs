@@ -0,0 +1,152 @@
+//! Currencies
+//!
+//! Symbols and decimals of the quote currencies, and conversion rates between them that are
+//! derived from the last prices of the feed.
+
+use std::collections::{HashMap, VecDeque};
+
+use ringbuffer::RingBuffer;
+
+use crate::sockets::ws_messages;
+
+/// `(code, symbol, decimals, symbol goes in front)`
+const CURRENCY_TABLE: [(&str, &str, usize, bool); 16] = [
+    ("USD", "$", 2, true),
+    ("EUR", "€", 2, true),
+    ("GBP", "£", 2, true),
+    ("JPY", "¥", 0, true),
+    ("CHF", "CHF ", 2, true),
+    ("CAD", "CA$", 2, true),
+    ("AUD", "A$", 2, true),
+    ("SGD", "S$", 2, true),
+    ("USDC", " USDC", 2, false),
+    ("USDT", " USDT", 2, false),
+    ("DAI", " DAI", 2, false),
+    ("EURC", " EURC", 2, false),
+    ("BTC", "₿", 8, true),
+    ("ETH", "Ξ", 6, true),
+    ("SOL", " SOL", 4, false),
+    ("DOGE", " DOGE", 2, false),
+];
+
+/// Decimals of currencies that arent in the table, they are probably crypto so a few more than fiat
+const UNKNOWN_DECIMALS: usize = 4;
+/// Prices below 1 get at least this many significant digits, so cheap coins dont show up as 0.00
+const MIN_SIGNIFICANT: i32 = 4;
+/// How many pairs a conversion may chain together
+const MAX_HOPS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Currency {
+    pub code: String,
+    pub symbol: String,
+    pub decimals: usize,
+    /// Is the symbol written in front of the number, like `$5`, or after it, like `5 USDT`
+    pub prefix: bool,
+}
+
+impl Currency {
+    /// Looks up the currency, unknown codes are written after the number
+    pub fn lookup(code: &str) -> Self {
+        let code = code.to_uppercase();
+        match CURRENCY_TABLE.iter().find(|(c, ..)| *c == code) {
+            Some((_, symbol, decimals, prefix)) => Self {
+                code,
+                symbol: symbol.to_string(),
+                decimals: *decimals,
+                prefix: *prefix,
+            },
+            None => Self {
+                symbol: format!(" {code}"),
+                code,
+                decimals: UNKNOWN_DECIMALS,
+                prefix: false,
+            },
+        }
+    }
+
+    /// The quote currency of a product like `BTC-EUR`
+    pub fn of_product(product: &str) -> Self {
+        Self::lookup(product.split('-').nth(1).unwrap_or("USD"))
+    }
+
+    /// The decimals that `v` gets shown with
+    pub fn decimals_for(&self, v: f64) -> usize {
+        let v = v.abs();
+        if v == 0.0 || v >= 1.0 {
+            return self.decimals;
+        }
+        let leading_zeros = -v.log10().floor() as i32 - 1;
+        self.decimals
+            .max((leading_zeros + MIN_SIGNIFICANT) as usize)
+    }
+
+    /// The number with the symbol, like `€1234.50`
+    pub fn format(&self, v: f64) -> String {
+        let n = format!("{v:.*}", self.decimals_for(v));
+        match self.prefix {
+            true => format!("{}{n}", self.symbol),
+            false => format!("{n}{}", self.symbol),
+        }
+    }
+}
+
+/// The last price of every product as `(base, quote, price)`
+fn last_prices() -> Vec<(String, String, f64)> {
+    ws_messages
+        .lock()
+        .iter()
+        .filter_map(|(product, buf)| {
+            let (base, quote) = product.split_once('-')?;
+            let price = buf.back()?.price.parse::<f64>().ok()?;
+            (price > 0.0).then(|| (base.to_string(), quote.to_string(), price))
+        })
+        .collect()
+}
+
+/// How much one unit of `from` is worth in `to`. Uses the direct pair if the feed has one,
+/// otherwise the cross rate over other currencies, e.g. USD to EUR via BTC-USD and BTC-EUR.
+/// `None` until the feed sent the needed prices.
+pub fn rate(from: &str, to: &str) -> Option<f64> {
+    rate_avoiding(from, to, &[])
+}
+
+/// Like [`rate`], but without using any pair of the `avoid` currencies. Comparing BTC-EUR with
+/// BTC-USD needs a EUR rate that doesnt come from those two pairs, otherwise they are always equal.
+pub fn rate_avoiding(from: &str, to: &str, avoid: &[&str]) -> Option<f64> {
+    if from.eq_ignore_ascii_case(to) {
+        return Some(1.0);
+    }
+
+    let mut edges: HashMap<String, Vec<(String, f64)>> = HashMap::new();
+    for (base, quote, price) in last_prices()
+        .into_iter()
+        .filter(|(b, q, _)| !avoid.contains(&b.as_str()) && !avoid.contains(&q.as_str()))
+    {
+        edges
+            .entry(base.clone())
+            .or_default()
+            .push((quote.clone(), price));
+        edges.entry(quote).or_default().push((base, 1.0 / price));
+    }
+
+    // breadth first, so the rate with the fewest hops wins
+    let (from, to) = (from.to_uppercase(), to.to_uppercase());
+    let mut queue = VecDeque::from([(from.clone(), 1.0, 0)]);
+    let mut seen = vec![from];
+    while let Some((cur, r, hops)) = queue.pop_front() {
+        if cur == to {
+            return Some(r);
+        }
+        if hops >= MAX_HOPS {
+            continue;
+        }
+        for (next, price) in edges.get(&cur).into_iter().flatten() {
+            if !seen.contains(next) {
+                seen.push(next.clone());
+                queue.push_back((next.clone(), r * price, hops + 1));
+            }
+        }
+    }
+    None
+}
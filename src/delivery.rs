@@ -0,0 +1,353 @@
+//! Alert delivery
+//!
+//! Fired alerts can be passed on to a shell command and to webhooks, configured in the `delivery`
+//! table of `alerts.toml`.
+//!
+//! ```toml
+//! [delivery]
+//! # runs with `sh -c`, the alert is in the STONKS_* environment variables
+//! command = 'notify-send "$STONKS_PRODUCT" "$STONKS_MESSAGE"'
+//!
+//! [[delivery.webhook]]
+//! url = "https://hooks.slack.com/services/..."
+//! format = "slack"
+//!
+//! [[delivery.webhook]]
+//! url = "http://localhost:8080/alerts"
+//! # json is the default, discord is also supported
+//! format = "json"
+//! retries = 5
+//! ```
+
+use std::{collections::BTreeMap, process::Stdio, sync::Arc, time::Duration};
+
+use anyhow::bail;
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+
+use crate::{alerts::FiredAlert, utils::convert_timestamp_to_locale};
+
+/// Wait time before the first retry, it doubles with every attempt
+const RETRY_BASE_MS: u64 = 500;
+/// Retries never wait longer than this
+const MAX_RETRY_DELAY_MS: u64 = 60_000;
+/// More retries than this are rejected, with the capped delay they would go on for ages
+pub const MAX_RETRIES: u32 = 10;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+fn default_retries() -> u32 {
+    3
+}
+
+/// The shape of the json that gets posted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookFormat {
+    /// Every field of the alert
+    #[default]
+    Json,
+    /// `{"text": ...}` for slack incoming webhooks
+    Slack,
+    /// `{"content": ...}` for discord webhooks
+    Discord,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Webhook {
+    pub url: String,
+    #[serde(default)]
+    pub format: WebhookFormat,
+    /// How often a failed request is retried, at most [`MAX_RETRIES`]
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+}
+
+impl Webhook {
+    fn payload(&self, alert: &FiredAlert) -> serde_json::Value {
+        match self.format {
+            WebhookFormat::Json => json!({
+                "product": alert.product,
+                "rule": alert.rule,
+                "value": alert.value,
+                "time": alert.time,
+                "message": message(alert),
+            }),
+            WebhookFormat::Slack => json!({ "text": message(alert) }),
+            WebhookFormat::Discord => json!({ "content": message(alert) }),
+        }
+    }
+
+    /// Posts the alert, retrying with backoff until a 2xx comes back or the retries are used up
+    async fn send(&self, client: &reqwest::Client, alert: &FiredAlert) -> anyhow::Result<()> {
+        let body = serde_json::to_string(&self.payload(alert))?;
+        let mut attempt = 0;
+
+        loop {
+            let res = client
+                .post(&self.url)
+                .header(reqwest::header::CONTENT_TYPE, "application/json")
+                .body(body.clone())
+                .timeout(REQUEST_TIMEOUT)
+                .send()
+                .await
+                .map_err(anyhow::Error::from)
+                .and_then(|r| match r.status().is_success() {
+                    true => Ok(()),
+                    false => Err(anyhow::anyhow!(
+                        "{} responded with {}",
+                        self.url,
+                        r.status()
+                    )),
+                });
+
+            match res {
+                Ok(()) => return Ok(()),
+                Err(e) if attempt >= self.retries => return Err(e),
+                Err(_) => {
+                    tokio::time::sleep(retry_delay(attempt)).await;
+                    attempt += 1;
+                }
+            }
+        }
+    }
+}
+
+/// How long to wait before the retry after `attempt` failed ones
+fn retry_delay(attempt: u32) -> Duration {
+    let ms = RETRY_BASE_MS.saturating_mul(1 << attempt.min(16));
+    Duration::from_millis(ms.min(MAX_RETRY_DELAY_MS))
+}
+
+/// Remembers the error of the target, or forgets its old one when it worked
+fn set_error(errors: &Mutex<BTreeMap<String, String>>, target: String, err: Option<String>) {
+    match err {
+        Some(e) => errors.lock().insert(target, e),
+        None => errors.lock().remove(&target),
+    };
+}
+
+/// The one line summary of an alert that the command and the chat webhooks get
+fn message(alert: &FiredAlert) -> String {
+    format!(
+        "{} ({:.2}) at {}",
+        alert.rule,
+        alert.value,
+        convert_timestamp_to_locale(alert.time as f64)
+    )
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Delivery {
+    /// Shell command that runs for every alert
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    #[serde(default, rename = "webhook", skip_serializing_if = "Vec::is_empty")]
+    pub webhooks: Vec<Webhook>,
+
+    /// The last error of every target whose last delivery failed, by the command or the url of
+    /// the webhook, shown in the alerts screen
+    #[serde(skip)]
+    errors: Arc<Mutex<BTreeMap<String, String>>>,
+    #[serde(skip)]
+    client: reqwest::Client,
+}
+
+impl Delivery {
+    /// The errors of the targets that failed last time
+    pub fn errors(&self) -> Vec<String> {
+        self.errors.lock().values().cloned().collect()
+    }
+
+    pub fn validate(&self) -> anyhow::Result<()> {
+        for hook in self.webhooks.iter() {
+            if hook.retries > MAX_RETRIES {
+                bail!(
+                    "delivery.webhook {}: retries can be at most {MAX_RETRIES}",
+                    hook.url
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Passes the alert on to every target, this happens in the background so the tui doesnt
+    /// block on slow hooks
+    pub fn deliver(&self, alert: &FiredAlert) {
+        if let Some(cmd) = &self.command {
+            let cmd = cmd.clone();
+            let alert = alert.clone();
+            let errors = self.errors.clone();
+            tokio::spawn(async move {
+                let status = tokio::process::Command::new("sh")
+                    .arg("-c")
+                    .arg(&cmd)
+                    .env("STONKS_PRODUCT", &alert.product)
+                    .env("STONKS_RULE", &alert.rule)
+                    .env("STONKS_VALUE", alert.value.to_string())
+                    .env("STONKS_TIME", alert.time.to_string())
+                    .env("STONKS_MESSAGE", message(&alert))
+                    .stdin(Stdio::null())
+                    .stdout(Stdio::null())
+                    .stderr(Stdio::null())
+                    .status()
+                    .await;
+
+                let err = match status {
+                    Ok(s) if s.success() => None,
+                    Ok(s) => Some(format!("Alert command failed with {s}")),
+                    Err(e) => Some(format!("Alert command failed: {e}")),
+                };
+                set_error(&errors, cmd, err);
+            });
+        }
+
+        for hook in self.webhooks.iter() {
+            let hook = hook.clone();
+            let alert = alert.clone();
+            let client = self.client.clone();
+            let errors = self.errors.clone();
+            tokio::spawn(async move {
+                let err = hook.send(&client, &alert).await.err();
+                set_error(
+                    &errors,
+                    hook.url,
+                    err.map(|e| format!("Webhook failed: {e}")),
+                );
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    use super::*;
+
+    /// A local http server that answers every request with `status` and keeps the bodies
+    async fn stub(status: u16) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let bodies = Arc::new(Mutex::new(vec![]));
+        let seen = bodies.clone();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let mut raw = vec![];
+                let mut buf = [0; 4096];
+                while let Ok(n @ 1..) = socket.read(&mut buf).await {
+                    raw.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&raw);
+                    let Some((head, body)) = text.split_once("\r\n\r\n") else {
+                        continue;
+                    };
+                    let len = head
+                        .lines()
+                        .find_map(|l| {
+                            let l = l.to_lowercase();
+                            l.strip_prefix("content-length:")?.trim().parse().ok()
+                        })
+                        .unwrap_or(0);
+                    if body.len() >= len {
+                        seen.lock().push(body.to_string());
+                        break;
+                    }
+                }
+                let res = format!(
+                    "HTTP/1.1 {status} Stub\r\ncontent-length: 0\r\nconnection: close\r\n\r\n"
+                );
+                let _ = socket.write_all(res.as_bytes()).await;
+            }
+        });
+        (url, bodies)
+    }
+
+    fn alert() -> FiredAlert {
+        FiredAlert {
+            time: 0,
+            product: "BTC-USD".into(),
+            rule: "BTC-USD > 70000".into(),
+            value: 70001.5,
+            done: true,
+        }
+    }
+
+    fn hook(url: &str, format: WebhookFormat, retries: u32) -> Webhook {
+        Webhook {
+            url: url.to_string(),
+            format,
+            retries,
+        }
+    }
+
+    #[tokio::test]
+    async fn posts_chat_formats() {
+        let (url, bodies) = stub(200).await;
+        let client = reqwest::Client::new();
+        for format in [WebhookFormat::Slack, WebhookFormat::Discord] {
+            hook(&url, format, 0).send(&client, &alert()).await.unwrap();
+        }
+
+        let bodies = bodies
+            .lock()
+            .iter()
+            .map(|b| serde_json::from_str::<serde_json::Value>(b).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            bodies,
+            [
+                json!({ "text": message(&alert()) }),
+                json!({ "content": message(&alert()) })
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn stops_retrying_at_the_cap() {
+        let (url, bodies) = stub(500).await;
+        let res = hook(&url, WebhookFormat::Json, 2)
+            .send(&reqwest::Client::new(), &alert())
+            .await;
+
+        assert!(res.unwrap_err().to_string().contains("500"));
+        assert_eq!(bodies.lock().len(), 3);
+    }
+
+    #[test]
+    fn caps_the_retry_delay() {
+        assert_eq!(retry_delay(0), Duration::from_millis(RETRY_BASE_MS));
+        assert_eq!(retry_delay(1), Duration::from_millis(RETRY_BASE_MS * 2));
+        assert_eq!(
+            retry_delay(u32::MAX),
+            Duration::from_millis(MAX_RETRY_DELAY_MS)
+        );
+    }
+
+    #[tokio::test]
+    async fn keeps_an_error_per_target() {
+        let (failing, _) = stub(404).await;
+        let (working, _) = stub(204).await;
+        let delivery = Delivery {
+            webhooks: vec![
+                hook(&failing, WebhookFormat::Json, 0),
+                hook(&working, WebhookFormat::Json, 0),
+            ],
+            ..Default::default()
+        };
+        delivery.deliver(&alert());
+
+        for _ in 0..100 {
+            if !delivery.errors().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let errors = delivery.errors();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].contains(&failing) && errors[0].contains("404"));
+    }
+}
@@ -7,14 +7,19 @@ use futures::{FutureExt, StreamExt};
 use ratatui::crossterm::event::Event as CrosstermEvent;
 use tokio::sync::mpsc;
 
-use crate::sockets::WsMessage;
-
-// ok for some reason i cant figure out, when we have it on 30fps, it stops users from inputing
-pub const TICK_RATE: u64 = 500;
+use crate::{
+    app::Screen,
+    config::{DEFAULT_FRAME_RATE, DEFAULT_TICK_RATE_MS},
+    indicators::IndicatorKind,
+    prompt::PromptTarget,
+    sockets::WsMessage,
+};
 
 #[derive(Clone, Debug)]
 pub enum Event {
     Tick,
+    /// Time to draw the next frame
+    Render,
     Crossterm(CrosstermEvent),
     App(AppEvent),
 }
@@ -22,11 +27,63 @@ pub enum Event {
 #[derive(Clone, Debug)]
 pub enum AppEvent {
     /// New message from websocket
-    WSMessage(WsMessage),
+    WSMessage(Box<WsMessage>),
     /// Inc the multiplier thats applied on the price
     IncMult(bool),
     /// Dec the multiplier thats applied on the price
     DecMult(bool),
+    /// Select the next chart
+    FocusNext,
+    /// Select the previous chart
+    FocusPrev,
+    /// Maximize the selected chart or restore the layout
+    ToggleMaximize,
+    /// Switch to the next body layout
+    CycleLayout,
+    /// Show or hide an indicator on the selected chart
+    ToggleIndicator(IndicatorKind),
+    /// Switch the oscillator pane of the selected chart to the next oscillator
+    CycleOscillator,
+    /// Select the next indicator of the selected chart, that's the one whose period changes
+    SelectIndicator,
+    /// Change the period of the selected indicator on the selected chart
+    ChangeIndicatorPeriod(isize),
+    /// Move the selection of the overview table by n rows
+    OverviewSelect(isize),
+    /// Sort the overview by the next column, or reverse the order if true
+    OverviewSort(bool),
+    /// Show the chart of the given product maximized
+    OpenChart(String),
+    /// Switch to the next cost basis method of the ledger
+    CycleCostBasis,
+    /// Switch to the given screen, or back to the charts if it is already shown
+    ToggleScreen(Screen),
+    /// Move the selection of the alert rules by n rows
+    AlertSelect(isize),
+    /// Remove the selected alert rule
+    AlertDelete,
+    /// Snooze the selected alert rule, or wake it up again
+    AlertSnooze,
+    /// Switch the selected alert rule between one-shot and repeating
+    AlertToggleRepeat,
+    /// Open the prompt, prefilled with the given text
+    OpenPrompt(PromptTarget, String),
+    /// The prompt got submitted
+    SubmitPrompt(PromptTarget, String),
+    /// Open the product search
+    OpenPicker,
+    /// Start watching the product
+    AddProduct(String),
+    /// Switch to the next watchlist of the config
+    NextWatchlist,
+    /// The config file changed
+    ReloadConfig,
+    /// Turn the border animation on or off
+    ToggleAnimation,
+    /// Show or hide the keybindings
+    ToggleHelp,
+    /// Show or hide the frame timings
+    ToggleFrameStats,
     /// Quit the application.
     Quit,
 }
@@ -40,11 +97,22 @@ pub struct EventHandler {
     receiver: mpsc::UnboundedReceiver<Event>,
 }
 
+impl Default for EventHandler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl EventHandler {
     /// Constructs a new instance of [`EventHandler`] and spawns a new thread to handle events.
     pub fn new() -> Self {
+        Self::with_rates(DEFAULT_TICK_RATE_MS, DEFAULT_FRAME_RATE)
+    }
+
+    /// Like [`EventHandler::new`], with the tick rate in ms and the frame rate in frames per second
+    pub fn with_rates(tick_rate_ms: u64, frame_rate: u64) -> Self {
         let (sender, receiver) = mpsc::unbounded_channel();
-        let actor = EventTask::new(sender.clone());
+        let actor = EventTask::new(sender.clone(), tick_rate_ms, frame_rate);
         tokio::spawn(async { actor.run().await });
         Self { sender, receiver }
     }
@@ -65,6 +133,16 @@ impl EventHandler {
             .ok_or_eyre("Failed to receive event")
     }
 
+    /// The next event if one is already waiting, doesnt block
+    pub fn try_next(&mut self) -> Option<Event> {
+        self.receiver.try_recv().ok()
+    }
+
+    /// A sender for events from outside of the app, like the config watcher
+    pub fn sender(&self) -> mpsc::UnboundedSender<Event> {
+        self.sender.clone()
+    }
+
     /// Queue an app event to be sent to the event receiver.
     ///
     /// This is useful for sending events to the event handler which will be processed by the next
@@ -80,30 +158,37 @@ impl EventHandler {
 struct EventTask {
     /// Event sender channel.
     sender: mpsc::UnboundedSender<Event>,
+    tick_rate: time::Duration,
+    frame_rate: time::Duration,
 }
 
 impl EventTask {
     /// Constructs a new instance of [`EventThread`].
-    fn new(sender: mpsc::UnboundedSender<Event>) -> Self {
-        Self { sender }
+    fn new(sender: mpsc::UnboundedSender<Event>, tick_rate_ms: u64, frame_rate: u64) -> Self {
+        Self {
+            sender,
+            tick_rate: time::Duration::from_millis(tick_rate_ms.max(1)),
+            frame_rate: time::Duration::from_secs_f64(1.0 / frame_rate.max(1) as f64),
+        }
     }
 
     /// Runs the event thread.
     ///
-    /// This function emits tick events at a fixed rate and polls for crossterm events in between.
+    /// This function emits tick and render events at a fixed rate and polls for crossterm events in
+    /// between.
     async fn run(self) -> color_eyre::Result<()> {
         let mut reader = crossterm::event::EventStream::new();
 
-        //                                                                                  replace
-        //                                                                                  with
-        //                                                                                  the
-        //                                                                                  opts
-        //                                                                                  laters
-        let mut tick = tokio::time::interval(time::Duration::from_millis(TICK_RATE));
+        let mut tick = tokio::time::interval(self.tick_rate);
+        let mut render = tokio::time::interval(self.frame_rate);
+        // a slow frame shouldnt be followed by a burst of frames to catch up
+        render.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
         loop {
             let crossterm_event = reader.next().fuse();
+            // input first, when a key and a frame are both ready the key goes out before it
             tokio::select! {
+                biased;
                 Some(Ok(evt)) = crossterm_event => {
                     self.send(Event::Crossterm(evt));
                 }
@@ -113,6 +198,9 @@ impl EventTask {
                 _ = tick.tick() => {
                     self.send(Event::Tick);
                 }
+                _ = render.tick() => {
+                    self.send(Event::Render);
+                }
             };
         }
         Ok(())
@@ -0,0 +1,102 @@
+//! Frame timings
+//!
+//! How often frames actually get drawn and how long drawing takes, shown in a small overlay in the
+//! top left corner of the body. Frames are only drawn when something changed, so the drawn frame
+//! rate is usually far below the configured one.
+
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    style::Stylize,
+    text::Line,
+    widgets::{Clear, Paragraph},
+};
+
+use crate::theme::Theme;
+
+/// How many of the last frames the draw times are averaged over
+const KEEP_FRAMES: usize = 120;
+
+#[derive(Debug, Clone, Default)]
+pub struct FrameStats {
+    /// When the last frames were drawn and how long each took, oldest first
+    frames: VecDeque<(Instant, Duration)>,
+    pub drawn: u64,
+    /// Frames that werent drawn because nothing changed
+    pub skipped: u64,
+    /// Events that were handled before the frame they arrived in, instead of after it
+    pub early_events: u64,
+}
+
+impl FrameStats {
+    /// A frame was drawn, `started` is when drawing it began
+    pub fn record(&mut self, started: Instant) {
+        self.frames.push_back((started, started.elapsed()));
+        if self.frames.len() > KEEP_FRAMES {
+            self.frames.pop_front();
+        }
+        self.drawn += 1;
+    }
+
+    /// Frames drawn in the last second
+    pub fn fps(&self) -> usize {
+        self.frames
+            .iter()
+            .filter(|(at, _)| at.elapsed() <= Duration::from_secs(1))
+            .count()
+    }
+
+    /// Average and slowest draw time of the kept frames
+    pub fn draw_times(&self) -> (Duration, Duration) {
+        let max = self
+            .frames
+            .iter()
+            .map(|(_, d)| *d)
+            .max()
+            .unwrap_or_default();
+        let sum = self.frames.iter().map(|(_, d)| *d).sum::<Duration>();
+        (sum / self.frames.len().max(1) as u32, max)
+    }
+}
+
+pub fn render_frame_stats(
+    frame: &mut Frame,
+    area: Rect,
+    stats: &FrameStats,
+    frame_rate: u64,
+    theme: &Theme,
+) {
+    let (avg, max) = stats.draw_times();
+    let ms = |d: Duration| format!("{:.1}ms", d.as_secs_f64() * 1000.0);
+    let rows = [
+        ("fps", format!("{} / {frame_rate}", stats.fps())),
+        ("draw avg", ms(avg)),
+        ("draw max", ms(max)),
+        ("drawn", stats.drawn.to_string()),
+        ("skipped", stats.skipped.to_string()),
+        ("early events", stats.early_events.to_string()),
+    ];
+    let lines = rows
+        .iter()
+        .map(|(label, v)| {
+            Line::from(vec![
+                format!("{label:<13}").fg(theme.muted),
+                v.clone().fg(theme.label),
+            ])
+        })
+        .collect::<Vec<_>>();
+
+    let width = (lines.iter().map(|l| l.width()).max().unwrap_or(0) as u16 + 2)
+        .max(16)
+        .min(area.width);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let area = Rect::new(area.x, area.y, width, height);
+    frame.render_widget(Clear, area);
+    frame.render_widget(theme.background_block(), area);
+    frame.render_widget(theme.panel(Paragraph::new(lines)).title("Frames"), area);
+}
@@ -3,21 +3,141 @@
 //! This module provides functionality to wrap any ratatui widget with a customizable
 //! gradient border using rounded corners.
 
-use ratatui::{buffer::Buffer, layout::Rect, style::Color, widgets::Widget};
+use std::str::FromStr;
+
+use colorgrad::{BlendMode, Gradient, GradientBuilder, LinearGradient, preset};
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Color,
+    widgets::{StatefulWidget, Widget},
+};
+
+/// Presets that `GradientConfig::preset` knows, the first four are our own, the rest come
+/// from colorgrad
+pub const PRESETS: [&str; 22] = [
+    "ocean", "sunset", "aurora", "neon", "viridis", "inferno", "magma", "plasma", "cividis",
+    "turbo", "rainbow", "sinebow", "warm", "cool", "cubehelix", "spectral", "blues", "greens",
+    "greys", "oranges", "purples", "reds",
+];
+/// How many colors get taken from a colorgrad preset, enough that the border looks the same
+const PRESET_STOPS: usize = 12;
+
+/// How colors get mixed between two stops
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Blend {
+    /// Straight between the rgb values, the middle can get muddy
+    #[default]
+    Rgb,
+    /// Perceptual, the middle is as bright as the ends
+    Oklab,
+}
 
-/// Interpolates between two RGB colors based on a ratio
-pub fn interpolate_color(start: Color, end: Color, ratio: f32) -> Color {
-    match (start, end) {
-        (Color::Rgb(r1, g1, b1), Color::Rgb(r2, g2, b2)) => {
-            let r = (r1 as f32 + (r2 as f32 - r1 as f32) * ratio) as u8;
-            let g = (g1 as f32 + (g2 as f32 - g1 as f32) * ratio) as u8;
-            let b = (b1 as f32 + (b2 as f32 - b1 as f32) * ratio) as u8;
-            Color::Rgb(r, g, b)
+impl FromStr for Blend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "rgb" => Ok(Blend::Rgb),
+            "oklab" => Ok(Blend::Oklab),
+            _ => Err(format!("unknown blend '{s}', expected rgb or oklab")),
         }
-        _ => start,
     }
 }
 
+impl From<Blend> for BlendMode {
+    fn from(blend: Blend) -> Self {
+        match blend {
+            Blend::Rgb => BlendMode::Rgb,
+            Blend::Oklab => BlendMode::Oklab,
+        }
+    }
+}
+
+/// The rgb value of any color, named and indexed ones get the xterm defaults. `Reset` is
+/// whatever the terminal uses, so there is nothing to blend with.
+pub fn to_rgb(color: Color) -> Option<(u8, u8, u8)> {
+    const ANSI: [(u8, u8, u8); 16] = [
+        (0, 0, 0),
+        (205, 0, 0),
+        (0, 205, 0),
+        (205, 205, 0),
+        (0, 0, 238),
+        (205, 0, 205),
+        (0, 205, 205),
+        (229, 229, 229),
+        (127, 127, 127),
+        (255, 0, 0),
+        (0, 255, 0),
+        (255, 255, 0),
+        (92, 92, 255),
+        (255, 0, 255),
+        (0, 255, 255),
+        (255, 255, 255),
+    ];
+    let index = match color {
+        Color::Reset => return None,
+        Color::Rgb(r, g, b) => return Some((r, g, b)),
+        Color::Black => 0,
+        Color::Red => 1,
+        Color::Green => 2,
+        Color::Yellow => 3,
+        Color::Blue => 4,
+        Color::Magenta => 5,
+        Color::Cyan => 6,
+        Color::Gray => 7,
+        Color::DarkGray => 8,
+        Color::LightRed => 9,
+        Color::LightGreen => 10,
+        Color::LightYellow => 11,
+        Color::LightBlue => 12,
+        Color::LightMagenta => 13,
+        Color::LightCyan => 14,
+        Color::White => 15,
+        Color::Indexed(i) => i,
+    };
+    Some(match index {
+        0..=15 => ANSI[index as usize],
+        // the 6x6x6 color cube
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let i = index - 16;
+            (level(i / 36), level(i / 6 % 6), level(i % 6))
+        }
+        // and 24 grays
+        _ => {
+            let v = 8 + (index - 232) * 10;
+            (v, v, v)
+        }
+    })
+}
+
+fn to_colorgrad(color: Color) -> Option<colorgrad::Color> {
+    to_rgb(color).map(|(r, g, b)| colorgrad::Color::from_rgba8(r, g, b, 255))
+}
+
+fn from_colorgrad(color: colorgrad::Color) -> Color {
+    let [r, g, b, _] = color.to_rgba8();
+    Color::Rgb(r, g, b)
+}
+
+/// Interpolates between two colors based on a ratio
+pub fn interpolate_color(start: Color, end: Color, ratio: f32) -> Color {
+    blend_color(start, end, ratio, Blend::Rgb)
+}
+
+/// Interpolates between two colors, `Reset` cant be blended and stays the start color
+pub fn blend_color(start: Color, end: Color, ratio: f32, blend: Blend) -> Color {
+    let (Some(a), Some(b)) = (to_colorgrad(start), to_colorgrad(end)) else {
+        return start;
+    };
+    let ratio = ratio.clamp(0.0, 1.0);
+    from_colorgrad(match blend {
+        Blend::Rgb => a.interpolate_rgb(&b, ratio),
+        Blend::Oklab => a.interpolate_oklab(&b, ratio),
+    })
+}
+
 /// Configuration for gradient colors on borders
 #[derive(Clone, Debug)]
 pub struct GradientConfig {
@@ -33,6 +153,10 @@ pub struct GradientConfig {
     /// This starts at the BOTTOM LEFT corner and goes up to the top left corner
     pub left_start: Color,
     pub left_end: Color,
+    /// Colors that flow around the whole border clockwise from the top left corner and back
+    /// into the first one, the sides above are ignored for drawing when there are any
+    pub stops: Vec<Color>,
+    pub blend: Blend,
 }
 
 impl Default for GradientConfig {
@@ -46,12 +170,15 @@ impl Default for GradientConfig {
             bottom_end: Color::Rgb(255, 0, 255),
             left_start: Color::Rgb(255, 0, 255),
             left_end: Color::Rgb(255, 0, 0),
+            stops: Vec::new(),
+            blend: Blend::Rgb,
         }
     }
 }
 
 impl GradientConfig {
     /// Creates a new gradient configuration with default values
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         top_start: Color,
         top_end: Color,
@@ -71,6 +198,8 @@ impl GradientConfig {
             bottom_end,
             left_start,
             left_end,
+            stops: Vec::new(),
+            blend: Blend::Rgb,
         }
     }
 
@@ -84,6 +213,8 @@ impl GradientConfig {
             bottom_end: c,
             left_start: c,
             left_end: c,
+            stops: Vec::new(),
+            blend: Blend::Rgb,
         }
     }
 
@@ -99,6 +230,8 @@ impl GradientConfig {
             bottom_end: inter,
             left_start: inter,
             left_end: c,
+            stops: Vec::new(),
+            blend: Blend::Rgb,
         }
     }
 
@@ -112,15 +245,184 @@ impl GradientConfig {
             bottom_end: bot_l,
             left_start: bot_l,
             left_end: top_l,
+            stops: Vec::new(),
+            blend: Blend::Rgb,
+        }
+    }
+
+    /// Any number of colors flowing around the border, the sides get the colors at the corners
+    /// so `top_start` etc. still say roughly what it looks like
+    pub fn new_stops(stops: &[Color]) -> Self {
+        let mut config = Self::new_1(stops.first().copied().unwrap_or(Color::Reset));
+        config.stops = stops.to_vec();
+        config.sample_corners()
+    }
+
+    /// How the colors get mixed
+    pub fn blend(mut self, blend: Blend) -> Self {
+        self.blend = blend;
+        match self.stops.is_empty() {
+            true => self,
+            false => self.sample_corners(),
+        }
+    }
+
+    fn sample_corners(self) -> Self {
+        let Some(g) = self.around() else {
+            return self;
+        };
+        let at = |t: f32| from_colorgrad(g.at(t));
+        Self {
+            stops: self.stops.clone(),
+            blend: self.blend,
+            ..Self::new_4(at(0.0), at(0.25), at(0.5), at(0.75))
+        }
+    }
+
+    /// The gradient that goes around the whole border, it ends in the color it starts with so
+    /// there is no jump in the top left corner. Without stops the corners are used.
+    fn around(&self) -> Option<LinearGradient> {
+        let corners = [
+            self.top_start,
+            self.right_start,
+            self.bottom_start,
+            self.left_start,
+        ];
+        let stops = match self.stops.is_empty() {
+            true => &corners[..],
+            false => &self.stops[..],
+        };
+        let mut colors: Vec<colorgrad::Color> =
+            stops.iter().filter_map(|c| to_colorgrad(*c)).collect();
+        colors.push(colors.first()?.clone());
+        GradientBuilder::new()
+            .colors(&colors)
+            .mode(self.blend.into())
+            .build::<LinearGradient>()
+            .ok()
+    }
+
+    /// Deep blue into turquoise
+    pub fn ocean() -> Self {
+        Self::new_stops(&[
+            Color::Rgb(11, 61, 145),
+            Color::Rgb(30, 144, 255),
+            Color::Rgb(0, 206, 209),
+            Color::Rgb(127, 255, 212),
+        ])
+        .blend(Blend::Oklab)
+    }
+
+    /// Red, orange, yellow and a bit of purple
+    pub fn sunset() -> Self {
+        Self::new_stops(&[
+            Color::Rgb(255, 94, 98),
+            Color::Rgb(255, 153, 102),
+            Color::Rgb(255, 216, 111),
+            Color::Rgb(200, 109, 215),
+        ])
+        .blend(Blend::Oklab)
+    }
+
+    /// Green through cyan into violet
+    pub fn aurora() -> Self {
+        Self::new_stops(&[
+            Color::Rgb(0, 255, 135),
+            Color::Rgb(96, 239, 255),
+            Color::Rgb(138, 43, 226),
+        ])
+        .blend(Blend::Oklab)
+    }
+
+    /// Loud pink, cyan and yellow
+    pub fn neon() -> Self {
+        Self::new_stops(&[
+            Color::Rgb(255, 0, 200),
+            Color::Rgb(0, 255, 255),
+            Color::Rgb(255, 255, 0),
+        ])
+    }
+
+    /// One of the `PRESETS` by name
+    pub fn preset(name: &str) -> Option<Self> {
+        let g: Box<dyn Gradient> = match name.to_lowercase().as_str() {
+            "ocean" => return Some(Self::ocean()),
+            "sunset" => return Some(Self::sunset()),
+            "aurora" => return Some(Self::aurora()),
+            "neon" => return Some(Self::neon()),
+            "viridis" => preset::viridis().boxed(),
+            "inferno" => preset::inferno().boxed(),
+            "magma" => preset::magma().boxed(),
+            "plasma" => preset::plasma().boxed(),
+            "cividis" => preset::cividis().boxed(),
+            "turbo" => preset::turbo().boxed(),
+            "rainbow" => preset::rainbow().boxed(),
+            "sinebow" => preset::sinebow().boxed(),
+            "warm" => preset::warm().boxed(),
+            "cool" => preset::cool().boxed(),
+            "cubehelix" => preset::cubehelix_default().boxed(),
+            "spectral" => preset::spectral().boxed(),
+            "blues" => preset::blues().boxed(),
+            "greens" => preset::greens().boxed(),
+            "greys" => preset::greys().boxed(),
+            "oranges" => preset::oranges().boxed(),
+            "purples" => preset::purples().boxed(),
+            "reds" => preset::reds().boxed(),
+            _ => return None,
+        };
+        let stops: Vec<Color> = g.colors(PRESET_STOPS).into_iter().map(from_colorgrad).collect();
+        Some(Self::new_stops(&stops))
+    }
+}
+
+/// The characters a border is drawn with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BorderSymbols {
+    pub top_left: char,
+    pub top_right: char,
+    pub bottom_left: char,
+    pub bottom_right: char,
+    pub horizontal: char,
+    pub vertical: char,
+    /// Before and after the title
+    pub title_left: char,
+    pub title_right: char,
+}
+
+impl BorderSymbols {
+    pub const ROUNDED: Self = Self::new(['╭', '╮', '╰', '╯', '─', '│', '┤', '├']);
+    pub const PLAIN: Self = Self::new(['┌', '┐', '└', '┘', '─', '│', '┤', '├']);
+    pub const THICK: Self = Self::new(['┏', '┓', '┗', '┛', '━', '┃', '┫', '┣']);
+    pub const DOUBLE: Self = Self::new(['╔', '╗', '╚', '╝', '═', '║', '╣', '╠']);
+    /// For terminals and fonts without box drawing characters
+    pub const ASCII: Self = Self::new(['+', '+', '+', '+', '-', '|', '[', ']']);
+
+    /// In the order of the fields
+    const fn new(c: [char; 8]) -> Self {
+        Self {
+            top_left: c[0],
+            top_right: c[1],
+            bottom_left: c[2],
+            bottom_right: c[3],
+            horizontal: c[4],
+            vertical: c[5],
+            title_left: c[6],
+            title_right: c[7],
         }
     }
+}
 
+impl Default for BorderSymbols {
+    fn default() -> Self {
+        Self::ROUNDED
+    }
 }
 
 /// Wrapper that renders any widget with a customizable gradient border
 ///
 /// This struct wraps any ratatui widget and draws a gradient border around it
-/// using rounded corners. The gradient colors are fully customizable.
+/// using rounded corners, or any other [`BorderSymbols`]. The gradient colors are fully
+/// customizable.
 ///
 /// # Examples
 ///
@@ -138,7 +440,13 @@ impl GradientConfig {
 pub struct GradientWrapper<W> {
     widget: W,
     title: Option<String>,
+    title_color: Color,
     gradient_config: GradientConfig,
+    /// How far the colors are turned clockwise around the border, 1.0 is once around
+    rotation: Option<f32>,
+    /// Every border color gets mixed with this color by the amount, from 0 to 1
+    pulse: Option<(Color, f32)>,
+    symbols: BorderSymbols,
 }
 
 impl<W> GradientWrapper<W> {
@@ -147,7 +455,11 @@ impl<W> GradientWrapper<W> {
         Self {
             widget,
             title: None,
+            title_color: Color::White,
             gradient_config: GradientConfig::default(),
+            rotation: None,
+            pulse: None,
+            symbols: BorderSymbols::default(),
         }
     }
 
@@ -157,12 +469,37 @@ impl<W> GradientWrapper<W> {
         self
     }
 
+    /// Sets the color of the title
+    pub fn title_color(mut self, color: Color) -> Self {
+        self.title_color = color;
+        self
+    }
+
     /// Sets the gradient configuration for the border colors
     pub fn gradient_colors(mut self, config: GradientConfig) -> Self {
         self.gradient_config = config;
         self
     }
 
+    /// Turns the colors clockwise around the border, 1.0 is once around and back to the start
+    pub fn rotation(mut self, turns: f32) -> Self {
+        self.rotation = Some(turns.rem_euclid(1.0));
+        self
+    }
+
+    /// Mixes the whole border with `color`, `strength` 0 doesnt change anything and 1 is only
+    /// `color`
+    pub fn pulse(mut self, color: Color, strength: f32) -> Self {
+        self.pulse = (strength > 0.0).then_some((color, strength.min(1.0)));
+        self
+    }
+
+    /// Sets the characters the border is drawn with
+    pub fn border_symbols(mut self, symbols: BorderSymbols) -> Self {
+        self.symbols = symbols;
+        self
+    }
+
     /// Draws the gradient border around the given area
     pub fn draw_gradient_border(&self, area: Rect, buf: &mut Buffer) {
         if area.width < 2 || area.height < 2 {
@@ -170,6 +507,7 @@ impl<W> GradientWrapper<W> {
         }
 
         let config = &self.gradient_config;
+        let symbols = &self.symbols;
 
         // Calculate corner colors by blending horizontal and vertical gradients
         let top_left_color = config.top_start; // Start of both gradients
@@ -192,83 +530,130 @@ impl<W> GradientWrapper<W> {
             0.5, // Blend both end colors
         );
 
-        // Draw ROUNDED corners WITH colors (using Unicode rounded corner characters)
-        buf.get_mut(area.left(), area.top())
-            .set_char('╭') // Rounded top-left
+        // Draw the corners WITH colors
+        buf[(area.left(), area.top())]
+            .set_char(symbols.top_left)
             .set_fg(top_left_color);
 
-        buf.get_mut(area.right() - 1, area.top())
-            .set_char('╮') // Rounded top-right
+        buf[(area.right() - 1, area.top())]
+            .set_char(symbols.top_right)
             .set_fg(top_right_color);
 
-        buf.get_mut(area.left(), area.bottom() - 1)
-            .set_char('╰') // Rounded bottom-left
+        buf[(area.left(), area.bottom() - 1)]
+            .set_char(symbols.bottom_left)
             .set_fg(bottom_left_color);
 
-        buf.get_mut(area.right() - 1, area.bottom() - 1)
-            .set_char('╯') // Rounded bottom-right
+        buf[(area.right() - 1, area.bottom() - 1)]
+            .set_char(symbols.bottom_right)
             .set_fg(bottom_right_color);
 
         // Draw top and bottom borders with horizontal gradient
         for x in area.left() + 1..area.right() - 1 {
             let ratio = (x - area.left() - 1) as f32 / (area.width - 2) as f32;
-            let color = interpolate_color(config.top_start, config.top_end, ratio);
-            let b_color =
-                interpolate_color(config.bottom_start, config.bottom_end, (ratio - 1.0).abs());
+            let color = blend_color(config.top_start, config.top_end, ratio, config.blend);
+            let b_color = blend_color(
+                config.bottom_start,
+                config.bottom_end,
+                (ratio - 1.0).abs(),
+                config.blend,
+            );
 
-            buf.get_mut(x, area.top()).set_char('─').set_fg(color);
+            buf[(x, area.top())].set_char(symbols.horizontal).set_fg(color);
 
-            buf.get_mut(x, area.bottom() - 1)
-                .set_char('─')
-                .set_fg(b_color);
+            buf[(x, area.bottom() - 1)].set_char(symbols.horizontal).set_fg(b_color);
         }
 
         // Draw left and right borders with vertical gradient
         for y in area.top() + 1..area.bottom() - 1 {
             let ratio = (y - area.top() - 1) as f32 / (area.height - 2) as f32;
-            let r_color = interpolate_color(config.right_start, config.right_end, ratio);
-            let color = interpolate_color(config.left_start, config.left_end, (ratio - 1.0).abs());
+            let r_color = blend_color(config.right_start, config.right_end, ratio, config.blend);
+            let color = blend_color(
+                config.left_start,
+                config.left_end,
+                (ratio - 1.0).abs(),
+                config.blend,
+            );
+
+            buf[(area.left(), y)].set_char(symbols.vertical).set_fg(color);
 
-            buf.get_mut(area.left(), y).set_char('│').set_fg(color);
+            buf[(area.right() - 1, y)].set_char(symbols.vertical).set_fg(r_color);
+        }
 
-            buf.get_mut(area.right() - 1, y)
-                .set_char('│')
-                .set_fg(r_color);
+        // Stops and rotations go over the sides, by how far along the border each cell is
+        let around = match config.stops.is_empty() && self.rotation.is_none() {
+            true => None,
+            false => config.around(),
+        };
+        if let Some(g) = around {
+            let perimeter = 2.0 * (area.width + area.height - 2) as f32;
+            let rotation = self.rotation.unwrap_or(0.0);
+            for (pos, cell) in border_cells(area) {
+                let t = (pos as f32 / perimeter - rotation).rem_euclid(1.0);
+                buf[cell].set_fg(from_colorgrad(g.at(t)));
+            }
+        }
+
+        if let Some((color, strength)) = self.pulse {
+            for (_, cell) in border_cells(area) {
+                let fg = buf[cell].fg;
+                buf[cell].set_fg(blend_color(fg, color, strength, config.blend));
+            }
         }
 
         // Draw title if provided
         if let Some(ref title) = self.title {
             let title_x = area.x + (area.width.saturating_sub(title.len() as u16 + 2)) / 2;
             if title_x < area.right() - 1 {
-                buf.get_mut(title_x, area.top()).set_char('┤');
+                buf[(title_x, area.top())].set_char(symbols.title_left);
                 for (i, ch) in title.chars().enumerate() {
                     if title_x + 1 + (i as u16) < area.right() - 1 {
-                        buf.get_mut(title_x + 1 + i as u16, area.top())
+                        buf[(title_x + 1 + i as u16, area.top())]
                             .set_char(ch)
-                            .set_fg(Color::White);
+                            .set_fg(self.title_color);
                     }
                 }
-                buf.get_mut(title_x + 1 + title.len() as u16, area.top())
-                    .set_char('├');
+                buf[(title_x + 1 + title.len() as u16, area.top())].set_char(symbols.title_right);
             }
         }
     }
 }
 
+/// Every cell of the border with how far along it is, clockwise from the top left corner
+fn border_cells(area: Rect) -> impl Iterator<Item = (u16, (u16, u16))> {
+    let (w, h) = (area.width - 1, area.height - 1);
+    let (left, top, right, bottom) = (area.left(), area.top(), area.right() - 1, area.bottom() - 1);
+    (0..w)
+        .map(move |i| (i, (left + i, top)))
+        .chain((0..h).map(move |i| (w + i, (right, top + i))))
+        .chain((0..w).map(move |i| (w + h + i, (right - i, bottom))))
+        .chain((0..h).map(move |i| (2 * w + h + i, (left, bottom - i))))
+}
+
+/// Calculate inner area (inside the border)
+fn inner_area(area: Rect) -> Rect {
+    Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    }
+}
+
 impl<W: Widget> Widget for GradientWrapper<W> {
     fn render(self, area: Rect, buf: &mut Buffer) {
         // Draw gradient border first
         self.draw_gradient_border(area, buf);
 
-        // Calculate inner area (inside the border)
-        let inner_area = Rect {
-            x: area.x + 1,
-            y: area.y + 1,
-            width: area.width.saturating_sub(2),
-            height: area.height.saturating_sub(2),
-        };
-
         // Render the wrapped widget in the inner area
-        self.widget.render(inner_area, buf);
+        self.widget.render(inner_area(area), buf);
+    }
+}
+
+impl<W: StatefulWidget> StatefulWidget for GradientWrapper<W> {
+    type State = W::State;
+
+    fn render(self, area: Rect, buf: &mut Buffer, state: &mut Self::State) {
+        self.draw_gradient_border(area, buf);
+        self.widget.render(inner_area(area), buf, state);
     }
 }
@@ -0,0 +1,191 @@
+//! 24h performance heatmap
+//!
+//! Every product is a tile of a squarified treemap, the size is the 24h quote volume and the color
+//! the percent change since `open_24h`.
+
+use ratatui::{
+    Frame,
+    layout::{Alignment, Rect},
+    style::{Color, Style},
+    widgets::{Block, Paragraph},
+};
+
+use crate::{
+    gradient_widget::interpolate_color,
+    overview::{MarketRow, human},
+    theme::Theme,
+};
+
+/// Changes at or above this many percent get the full color
+const FULL_COLOR_AT: f64 = 5.0;
+/// Tiles without any change, the tile text is always white so it has to stay dark
+const NEUTRAL_COLOR: Color = Color::Rgb(60, 60, 60);
+
+#[derive(Debug, Clone, Copy)]
+struct RectF {
+    x: f64,
+    y: f64,
+    w: f64,
+    h: f64,
+}
+
+/// The worst aspect ratio of a row of tiles laid out along `side`
+fn worst(row: &[f64], side: f64) -> f64 {
+    let sum = row.iter().sum::<f64>();
+    let max = row.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let min = row.iter().copied().fold(f64::INFINITY, f64::min);
+    let side_sq = side * side;
+    let sum_sq = sum * sum;
+
+    (side_sq * max / sum_sq).max(sum_sq / (side_sq * min))
+}
+
+/// Splits the area into one rect per weight, using the squarified treemap algorithm. The weights
+/// have to be sorted from the biggest to the smallest one.
+fn treemap(weights: &[f64], area: Rect) -> Vec<Rect> {
+    let total = weights.iter().sum::<f64>();
+    let area_size = area.width as f64 * area.height as f64;
+
+    // without any volume every tile gets the same size
+    let values = if total > 0.0 {
+        weights
+            .iter()
+            .map(|w| w / total * area_size)
+            .collect::<Vec<f64>>()
+    } else {
+        vec![area_size / weights.len().max(1) as f64; weights.len()]
+    };
+
+    let mut rect = RectF {
+        x: area.x as f64,
+        y: area.y as f64,
+        w: area.width as f64,
+        h: area.height as f64,
+    };
+    let mut out = Vec::with_capacity(values.len());
+    let mut i = 0;
+
+    while i < values.len() {
+        let side = rect.w.min(rect.h);
+        let mut end = i + 1;
+        while end < values.len() && worst(&values[i..=end], side) <= worst(&values[i..end], side) {
+            end += 1;
+        }
+
+        let row_sum = values[i..end].iter().sum::<f64>();
+        if rect.w >= rect.h {
+            // the row becomes a column on the left
+            let col_w = row_sum / rect.h;
+            let mut y = rect.y;
+            for v in &values[i..end] {
+                let h = v / col_w;
+                out.push(RectF {
+                    x: rect.x,
+                    y,
+                    w: col_w,
+                    h,
+                });
+                y += h;
+            }
+            rect.x += col_w;
+            rect.w -= col_w;
+        } else {
+            let row_h = row_sum / rect.w;
+            let mut x = rect.x;
+            for v in &values[i..end] {
+                let w = v / row_h;
+                out.push(RectF {
+                    x,
+                    y: rect.y,
+                    w,
+                    h: row_h,
+                });
+                x += w;
+            }
+            rect.y += row_h;
+            rect.h -= row_h;
+        }
+        i = end;
+    }
+
+    // rounding both edges instead of the size, so neighbours dont overlap or leave gaps
+    out.iter()
+        .map(|r| {
+            let x0 = r.x.round() as u16;
+            let y0 = r.y.round() as u16;
+            let x1 = (r.x + r.w).round() as u16;
+            let y1 = (r.y + r.h).round() as u16;
+            Rect::new(x0, y0, x1.saturating_sub(x0), y1.saturating_sub(y0))
+        })
+        .collect()
+}
+
+/// The down color of the theme for losses, the up color for gains and grey around zero
+pub fn change_color(change: f64, theme: &Theme) -> Color {
+    let t = (change / FULL_COLOR_AT).clamp(-1.0, 1.0);
+    let full = if t >= 0.0 { theme.up } else { theme.down };
+    interpolate_color(NEUTRAL_COLOR, full, t.abs() as f32)
+}
+
+pub fn render_heatmap(frame: &mut Frame, area: Rect, products: &[String], theme: &Theme) {
+    let mut rows = products
+        .iter()
+        .filter_map(|p| MarketRow::from_feed(p))
+        .map(|r| (r.volume_24h * r.price, r))
+        .collect::<Vec<(f64, MarketRow)>>();
+    rows.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    let wrapper = theme.panel(Block::new()).title("Heatmap - 24h");
+    frame.render_widget(wrapper, area);
+
+    let inner = Rect {
+        x: area.x + 1,
+        y: area.y + 1,
+        width: area.width.saturating_sub(2),
+        height: area.height.saturating_sub(2),
+    };
+
+    // products without any volume would get no tile at all, so everything gets at least a sliver
+    let min_weight = rows.first().map(|(w, _)| w * 0.01).unwrap_or(0.0);
+    let weights = rows
+        .iter()
+        .map(|(w, _)| w.max(min_weight))
+        .collect::<Vec<f64>>();
+    for (tile, (quote_volume, row)) in treemap(&weights, inner).iter().zip(rows.iter()) {
+        // leave the last column and row of every tile empty, so neighbours with the same color
+        // can still be told apart
+        let tile = Rect {
+            width: tile.width.saturating_sub((tile.width > 1) as u16),
+            height: tile.height.saturating_sub((tile.height > 1) as u16),
+            ..*tile
+        };
+        if tile.width == 0 || tile.height == 0 {
+            continue;
+        }
+
+        let text = match tile.height {
+            1 => format!("{} {:+.2}%", row.product, row.change),
+            2 => format!("{}\n{:+.2}%", row.product, row.change),
+            _ => format!(
+                "{}\n{:+.2}%\n{}",
+                row.product,
+                row.change,
+                human(*quote_volume)
+            ),
+        };
+
+        // center the text vertically in the tile
+        let lines = text.lines().count() as u16;
+        let pad = tile.height.saturating_sub(lines) / 2;
+        let text = format!("{}{text}", "\n".repeat(pad as usize));
+
+        frame.render_widget(
+            Paragraph::new(text).alignment(Alignment::Center).style(
+                Style::new()
+                    .bg(change_color(row.change, theme))
+                    .fg(Color::White),
+            ),
+            tile,
+        );
+    }
+}
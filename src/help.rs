@@ -0,0 +1,98 @@
+//! Help
+//!
+//! Every binding of the active keymap, generated from it so it is always what the keys really do.
+
+use ratatui::{
+    Frame,
+    layout::{Constraint, Flex, Layout, Margin, Rect},
+    style::Stylize,
+    text::Line,
+    widgets::{Clear, Paragraph},
+};
+
+use crate::{
+    keymap::{Action, Keymap, Mode},
+    theme::Theme,
+};
+
+/// Long key lists get cut, so one action cant push the descriptions out of the popup
+const MAX_KEYS_WIDTH: usize = 24;
+
+pub fn render_help(frame: &mut Frame, area: Rect, keymap: &Keymap, mode: Mode, theme: &Theme) {
+    let sections = Mode::ALL
+        .iter()
+        .map(|m| {
+            let rows = keymap
+                .bindings(*m)
+                .into_iter()
+                .map(|(action, keys)| {
+                    let keys = keys.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+                    (keys.join(", "), action.description())
+                })
+                .collect::<Vec<_>>();
+            (*m, rows)
+        })
+        .collect::<Vec<_>>();
+
+    let keys_width = sections
+        .iter()
+        .flat_map(|(_, rows)| rows.iter().map(|(k, _)| k.chars().count()))
+        .max()
+        .unwrap_or(0)
+        .min(MAX_KEYS_WIDTH);
+    let mut lines = vec![];
+    for (m, rows) in sections {
+        let title = match m == mode {
+            true => format!("{m} mode, active"),
+            false => format!("{m} mode"),
+        };
+        lines.push(Line::from(title.fg(theme.warning).bold()));
+        for (keys, description) in rows {
+            let keys = match keys.is_empty() {
+                true => format!("{:>keys_width$}", "-").fg(theme.muted),
+                false => {
+                    let keys = keys.chars().take(keys_width).collect::<String>();
+                    format!("{keys:>keys_width$}").fg(theme.info)
+                }
+            };
+            lines.push(Line::from(vec![
+                keys,
+                "  ".into(),
+                description.fg(theme.label),
+            ]));
+        }
+        lines.push(Line::default());
+    }
+    lines.pop();
+
+    // as many columns as it takes to fit the height
+    let height = area.height.saturating_sub(2).max(1) as usize;
+    let columns = lines.chunks(height).collect::<Vec<_>>();
+    let column_width = lines.iter().map(|l| l.width()).max().unwrap_or(0) as u16 + 2;
+    let width = (column_width * columns.len() as u16 + 2).min(area.width);
+    let rows = lines.len().min(height) as u16 + 2;
+
+    let [area] = Layout::horizontal([Constraint::Length(width)])
+        .flex(Flex::Center)
+        .areas(area);
+    let [area] = Layout::vertical([Constraint::Length(rows)])
+        .flex(Flex::Center)
+        .areas(area);
+    frame.render_widget(Clear, area);
+    frame.render_widget(theme.background_block(), area);
+    let inner = area.inner(Margin::new(1, 1));
+    frame.render_widget(
+        theme.panel(Paragraph::default()).title(
+            match keymap.hint(Mode::Normal, &[(Action::Help, "or any other key closes")]) {
+                h if h.is_empty() => "Keys - any key closes".to_string(),
+                h => format!("Keys - {h}"),
+            },
+        ),
+        area,
+    );
+    let column_areas =
+        Layout::horizontal(vec![Constraint::Length(column_width); columns.len()]).split(inner);
+    for (column, area) in columns.into_iter().zip(column_areas.iter()) {
+        frame.render_widget(Paragraph::new(column.to_vec()), *area);
+    }
+}
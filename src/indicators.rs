@@ -0,0 +1,385 @@
+//! Technical indicators
+//!
+//! Every indicator keeps its own running state and gets fed the ticker messages one by one, so we
+//! never have to recalculate anything over the whole buffer.
+
+use std::collections::VecDeque;
+
+use chrono::{DateTime, Utc};
+use ratatui::style::Color;
+use ringbuffer::RingBuffer;
+use serde::Deserialize;
+
+use crate::{
+    oscillators::{Oscillator, OscillatorKind},
+    sockets::ws_messages,
+};
+
+/// How many calculated points an indicator keeps, matches the size of the message buffer
+const MAX_POINTS: usize = 10000;
+
+pub const DEFAULT_SMA_PERIOD: usize = 20;
+pub const DEFAULT_EMA_PERIOD: usize = 9;
+pub const DEFAULT_BOLLINGER_PERIOD: usize = 20;
+pub const DEFAULT_BOLLINGER_K: f64 = 2.0;
+
+/// A line that gets drawn on the chart, with its name for the legend and its color
+pub type IndicatorLine = (String, Color, Vec<(f64, f64)>);
+
+/// The indicators by name, for the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IndicatorName {
+    Sma,
+    Ema,
+    Vwap,
+    Bollinger,
+}
+
+/// What new panels show and the periods the indicators start with, from `[chart.indicators]`
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndicatorDefaults {
+    pub shown: Vec<IndicatorName>,
+    pub sma_period: usize,
+    pub ema_period: usize,
+    pub bollinger_period: usize,
+    pub bollinger_k: f64,
+}
+
+impl Default for IndicatorDefaults {
+    fn default() -> Self {
+        Self {
+            shown: vec![],
+            sma_period: DEFAULT_SMA_PERIOD,
+            ema_period: DEFAULT_EMA_PERIOD,
+            bollinger_period: DEFAULT_BOLLINGER_PERIOD,
+            bollinger_k: DEFAULT_BOLLINGER_K,
+        }
+    }
+}
+
+impl IndicatorDefaults {
+    pub fn kind(&self, name: IndicatorName) -> IndicatorKind {
+        match name {
+            IndicatorName::Sma => IndicatorKind::Sma(self.sma_period),
+            IndicatorName::Ema => IndicatorKind::Ema(self.ema_period),
+            IndicatorName::Vwap => IndicatorKind::Vwap,
+            IndicatorName::Bollinger => {
+                IndicatorKind::Bollinger(self.bollinger_period, self.bollinger_k)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum IndicatorKind {
+    /// Simple moving average over the last n ticks
+    Sma(usize),
+    /// Exponential moving average over the last n ticks
+    Ema(usize),
+    /// Volume weighted average price since the app got started
+    Vwap,
+    /// Bollinger bands over the last n ticks with k standard deviations
+    Bollinger(usize, f64),
+}
+
+impl IndicatorKind {
+    /// The period of the indicator, if it has one
+    pub fn period(&self) -> Option<usize> {
+        match self {
+            IndicatorKind::Sma(p) | IndicatorKind::Ema(p) | IndicatorKind::Bollinger(p, _) => {
+                Some(*p)
+            }
+            IndicatorKind::Vwap => None,
+        }
+    }
+
+    /// Returns the same indicator with a different period, does nothing for the ones without one
+    pub fn with_period(self, period: usize) -> Self {
+        let period = period.max(2);
+        match self {
+            IndicatorKind::Sma(_) => IndicatorKind::Sma(period),
+            IndicatorKind::Ema(_) => IndicatorKind::Ema(period),
+            IndicatorKind::Bollinger(_, k) => IndicatorKind::Bollinger(period, k),
+            IndicatorKind::Vwap => IndicatorKind::Vwap,
+        }
+    }
+
+    /// Checks if both are the same indicator, ignoring the period
+    pub fn same_kind(&self, other: &IndicatorKind) -> bool {
+        std::mem::discriminant(self) == std::mem::discriminant(other)
+    }
+
+    fn label(&self) -> String {
+        match self {
+            IndicatorKind::Sma(p) => format!("SMA{p}"),
+            IndicatorKind::Ema(p) => format!("EMA{p}"),
+            IndicatorKind::Vwap => "VWAP".to_string(),
+            IndicatorKind::Bollinger(p, k) => format!("BB{p},{k}"),
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            IndicatorKind::Sma(_) => Color::Rgb(255, 215, 0),
+            IndicatorKind::Ema(_) => Color::Rgb(0, 200, 255),
+            IndicatorKind::Vwap => Color::Rgb(255, 120, 255),
+            IndicatorKind::Bollinger(..) => Color::Rgb(160, 160, 160),
+        }
+    }
+}
+
+/// The running state of an indicator
+#[derive(Debug, Clone)]
+enum State {
+    Sma { window: VecDeque<f64>, sum: f64 },
+    Ema { value: Option<f64>, seen: usize },
+    Vwap { price_volume: f64, volume: f64 },
+    Bollinger { window: VecDeque<f64> },
+}
+
+#[derive(Debug, Clone)]
+pub struct Indicator {
+    pub kind: IndicatorKind,
+    state: State,
+    /// One deque per line the indicator draws, bollinger bands have three, the others one
+    lines: Vec<VecDeque<(f64, f64)>>,
+}
+
+impl Indicator {
+    pub fn new(kind: IndicatorKind) -> Self {
+        let (state, line_count) = match kind {
+            IndicatorKind::Sma(_) => (
+                State::Sma {
+                    window: VecDeque::new(),
+                    sum: 0.0,
+                },
+                1,
+            ),
+            IndicatorKind::Ema(_) => (
+                State::Ema {
+                    value: None,
+                    seen: 0,
+                },
+                1,
+            ),
+            IndicatorKind::Vwap => (
+                State::Vwap {
+                    price_volume: 0.0,
+                    volume: 0.0,
+                },
+                1,
+            ),
+            IndicatorKind::Bollinger(..) => (
+                State::Bollinger {
+                    window: VecDeque::new(),
+                },
+                3,
+            ),
+        };
+
+        Self {
+            kind,
+            state,
+            lines: vec![VecDeque::new(); line_count],
+        }
+    }
+
+    /// Feeds the next tick into the indicator. Values are only produced once the indicator has
+    /// seen enough ticks to fill its period.
+    pub fn push(&mut self, time: f64, price: f64, size: f64) {
+        let values = match (&mut self.state, self.kind) {
+            (State::Sma { window, sum }, IndicatorKind::Sma(period)) => {
+                window.push_back(price);
+                *sum += price;
+                if window.len() > period {
+                    *sum -= window.pop_front().unwrap_or_default();
+                }
+
+                (window.len() == period).then(|| vec![*sum / period as f64])
+            }
+            (State::Ema { value, seen }, IndicatorKind::Ema(period)) => {
+                let alpha = 2.0 / (period as f64 + 1.0);
+                *seen += 1;
+                let next = match value {
+                    Some(v) => alpha * price + (1.0 - alpha) * *v,
+                    None => price,
+                };
+                *value = Some(next);
+
+                (*seen >= period).then(|| vec![next])
+            }
+            (
+                State::Vwap {
+                    price_volume,
+                    volume,
+                },
+                IndicatorKind::Vwap,
+            ) => {
+                *price_volume += price * size;
+                *volume += size;
+
+                (*volume > 0.0).then(|| vec![*price_volume / *volume])
+            }
+            (State::Bollinger { window }, IndicatorKind::Bollinger(period, k)) => {
+                window.push_back(price);
+                if window.len() > period {
+                    window.pop_front();
+                }
+
+                // straight from the window, running sums of squares lose too much precision at
+                // prices like 60000 and drift over a long session
+                (window.len() == period).then(|| {
+                    let mean = window.iter().sum::<f64>() / period as f64;
+                    let variance =
+                        window.iter().map(|p| (p - mean).powi(2)).sum::<f64>() / period as f64;
+                    let dev = variance.sqrt();
+                    vec![mean + k * dev, mean, mean - k * dev]
+                })
+            }
+            _ => None,
+        };
+
+        let Some(values) = values else {
+            return;
+        };
+
+        for (line, v) in self.lines.iter_mut().zip(values) {
+            line.push_back((time, v));
+            if line.len() > MAX_POINTS {
+                line.pop_front();
+            }
+        }
+    }
+
+    /// The lines that should be drawn, together with their name and color. The selected one gets
+    /// brackets around its name, its period is the one that gets changed.
+    pub fn datasets(&self, selected: bool) -> Vec<IndicatorLine> {
+        let label = match selected {
+            true => format!("[{}]", self.kind.label()),
+            false => self.kind.label(),
+        };
+        let names = match self.lines.len() {
+            3 => vec![format!("{label} up"), label.clone(), format!("{label} lo")],
+            _ => vec![label],
+        };
+
+        self.lines
+            .iter()
+            .zip(names)
+            .map(|(line, name)| (name, self.kind.color(), line.iter().copied().collect()))
+            .collect()
+    }
+}
+
+/// The indicators that are shown on a single chart panel
+#[derive(Debug, Clone, Default)]
+pub struct PanelIndicators {
+    pub indicators: Vec<Indicator>,
+    /// The indicator whose period gets changed
+    pub selected: usize,
+    /// The oscillator that is shown in the pane below the chart
+    pub oscillator: Option<Oscillator>,
+    /// The sequence of the last message that was fed into the indicators
+    last_sequence: usize,
+}
+
+impl PanelIndicators {
+    /// A panel with the indicators of the config
+    pub fn new(defaults: &IndicatorDefaults) -> Self {
+        Self {
+            indicators: defaults
+                .shown
+                .iter()
+                .map(|n| Indicator::new(defaults.kind(*n)))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    /// Shows the indicator and selects it, or hides it if its already shown
+    pub fn toggle(&mut self, kind: IndicatorKind) {
+        if let Some(idx) = self.indicators.iter().position(|i| i.kind.same_kind(&kind)) {
+            self.indicators.remove(idx);
+            if self.selected >= idx {
+                self.selected = self.selected.saturating_sub(1);
+            }
+            return;
+        }
+
+        self.indicators.push(Indicator::new(kind));
+        self.selected = self.indicators.len() - 1;
+        self.replay();
+    }
+
+    /// Selects the next indicator with a period
+    pub fn select_next(&mut self) {
+        let len = self.indicators.len();
+        self.selected = (1..=len)
+            .map(|i| (self.selected + i) % len)
+            .find(|i| self.indicators[*i].kind.period().is_some())
+            .unwrap_or(self.selected);
+    }
+
+    /// Switches to the next oscillator, or hides the pane after the last one
+    pub fn cycle_oscillator(&mut self) {
+        self.oscillator =
+            OscillatorKind::cycle(self.oscillator.as_ref().map(|o| o.kind)).map(Oscillator::new);
+        self.replay();
+    }
+
+    /// Changes the period of the selected indicator by `by`. The indicators get rebuilt from the
+    /// buffered messages.
+    pub fn change_period(&mut self, by: isize) {
+        let Some(ind) = self.indicators.get_mut(self.selected) else {
+            return;
+        };
+        if let Some(p) = ind.kind.period() {
+            ind.kind = ind.kind.with_period(p.saturating_add_signed(by));
+            self.replay();
+        }
+    }
+
+    /// Resets every indicator so the next update feeds the whole buffer into them again
+    fn replay(&mut self) {
+        for ind in self.indicators.iter_mut() {
+            *ind = Indicator::new(ind.kind);
+        }
+        if let Some(osc) = self.oscillator.as_mut() {
+            *osc = Oscillator::new(osc.kind);
+        }
+        self.last_sequence = 0;
+    }
+
+    /// Feeds every message that arrived since the last update into the indicators
+    pub fn update(&mut self, product: &str) {
+        if self.indicators.is_empty() && self.oscillator.is_none() {
+            return;
+        }
+
+        let msgs = ws_messages.lock();
+        let Some(buf) = msgs.get(product) else {
+            return;
+        };
+
+        let last_sequence = self.last_sequence;
+        for m in buf.iter().filter(|m| m.sequence > last_sequence) {
+            let Ok(time) = m.time.parse::<DateTime<Utc>>() else {
+                continue;
+            };
+            let Ok(price) = m.price.parse::<f64>() else {
+                continue;
+            };
+            let size = m.last_size.parse::<f64>().unwrap_or(0.0);
+
+            let time = time.timestamp_millis() as f64;
+            for ind in self.indicators.iter_mut() {
+                ind.push(time, price, size);
+            }
+            if let Some(osc) = self.oscillator.as_mut() {
+                osc.push(time, price);
+            }
+            self.last_sequence = m.sequence;
+        }
+    }
+}
@@ -0,0 +1,694 @@
+//! Keybindings
+//!
+//! Every key triggers an [`Action`], which one depends on the [`Mode`]: `normal` on the charts and
+//! most screens, `cursor` in the lists of the overview and the alerts and `command` while typing
+//! into the prompt or the product search. Cursor bindings win over the normal ones, keys that arent
+//! bound in cursor mode still do what they do in normal mode.
+//!
+//! The `keys` table of the config starts from a preset, `default` or `vim`, and changes single
+//! actions on top. A binding can be a sequence of keys separated by spaces, like `g h`.
+//!
+//! ```toml
+//! [keys]
+//! preset = "vim"
+//! quit = "x"
+//! focus_next = ["tab", "n"]
+//! heatmap = "g h"
+//!
+//! [keys.cursor]
+//! select_first = ["g g", "home"]
+//!
+//! [keys.command]
+//! cancel = ["esc", "ctrl-g"]
+//! ```
+//!
+//! `?` shows every binding of the active keymap, `:` opens the command line where every action
+//! can be typed by its name.
+
+use std::{borrow::Borrow, collections::HashMap, fmt::Display, str::FromStr};
+
+use anyhow::bail;
+use ratatui::crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Deserialize, Deserializer, de::Error};
+
+use crate::config::KeysConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Quit,
+    Help,
+    CommandLine,
+    Overview,
+    Heatmap,
+    Portfolio,
+    Ledger,
+    CycleCostBasis,
+    Alerts,
+    Arbitrage,
+    Comparison,
+    AddProduct,
+    NextWatchlist,
+    IncMult,
+    IncMultFine,
+    DecMult,
+    DecMultFine,
+    FocusNext,
+    FocusPrev,
+    ToggleMaximize,
+    CycleLayout,
+    ToggleSma,
+    ToggleEma,
+    ToggleVwap,
+    ToggleBollinger,
+    CycleOscillator,
+    SelectIndicator,
+    PeriodDown,
+    PeriodUp,
+    ToggleAnimation,
+    ToggleFrameStats,
+    SelectUp,
+    SelectDown,
+    SelectFirst,
+    SelectLast,
+    Open,
+    Sort,
+    SortReverse,
+    NewAlert,
+    EditAlert,
+    DeleteAlert,
+    SnoozeAlert,
+    ToggleRepeat,
+    Submit,
+    Complete,
+    Cancel,
+}
+
+impl Action {
+    /// What it does, for the help
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "show this help",
+            Action::CommandLine => "command line",
+            Action::Overview => "overview table",
+            Action::Heatmap => "heatmap",
+            Action::Portfolio => "portfolio",
+            Action::Ledger => "ledger",
+            Action::CycleCostBasis => "next cost basis method",
+            Action::Alerts => "alerts",
+            Action::Arbitrage => "arbitrage spreads",
+            Action::Comparison => "comparison chart",
+            Action::AddProduct => "search and add a product",
+            Action::NextWatchlist => "next watchlist",
+            Action::IncMult => "scale the chart up",
+            Action::IncMultFine => "scale the chart up a little",
+            Action::DecMult => "scale the chart down",
+            Action::DecMultFine => "scale the chart down a little",
+            Action::FocusNext => "focus the next chart",
+            Action::FocusPrev => "focus the previous chart",
+            Action::ToggleMaximize => "maximize the focused chart",
+            Action::CycleLayout => "next layout",
+            Action::ToggleSma => "toggle the sma",
+            Action::ToggleEma => "toggle the ema",
+            Action::ToggleVwap => "toggle the vwap",
+            Action::ToggleBollinger => "toggle the bollinger bands",
+            Action::CycleOscillator => "next oscillator",
+            Action::SelectIndicator => "select the indicator whose period changes",
+            Action::PeriodDown => "shorter period of the selected indicator",
+            Action::PeriodUp => "longer period of the selected indicator",
+            Action::ToggleAnimation => "toggle the border animation",
+            Action::ToggleFrameStats => "show the frame timings",
+            Action::SelectUp => "select the previous row",
+            Action::SelectDown => "select the next row",
+            Action::SelectFirst => "select the first row",
+            Action::SelectLast => "select the last row",
+            Action::Open => "open the chart of the row",
+            Action::Sort => "sort by the next column",
+            Action::SortReverse => "reverse the sorting",
+            Action::NewAlert => "new alert",
+            Action::EditAlert => "edit the alert",
+            Action::DeleteAlert => "delete the alert",
+            Action::SnoozeAlert => "snooze the alert",
+            Action::ToggleRepeat => "toggle repeating the alert",
+            Action::Submit => "submit",
+            Action::Complete => "complete the command",
+            Action::Cancel => "cancel",
+        }
+    }
+}
+
+/// Which bindings are active
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Mode {
+    /// The charts and every screen without a list
+    Normal,
+    /// Screens with a selected row
+    Cursor,
+    /// Typing into the prompt or the product search
+    Command,
+}
+
+impl Mode {
+    pub const ALL: [Mode; 3] = [Mode::Normal, Mode::Cursor, Mode::Command];
+
+    /// The actions that do something in the mode, in the order of the help
+    pub fn actions(&self) -> &'static [Action] {
+        use Action::*;
+        match self {
+            Mode::Normal => &[
+                Quit,
+                Help,
+                CommandLine,
+                Overview,
+                Heatmap,
+                Portfolio,
+                Ledger,
+                Alerts,
+                Arbitrage,
+                Comparison,
+                CycleCostBasis,
+                AddProduct,
+                NextWatchlist,
+                FocusNext,
+                FocusPrev,
+                ToggleMaximize,
+                CycleLayout,
+                IncMult,
+                IncMultFine,
+                DecMult,
+                DecMultFine,
+                ToggleSma,
+                ToggleEma,
+                ToggleVwap,
+                ToggleBollinger,
+                CycleOscillator,
+                SelectIndicator,
+                PeriodDown,
+                PeriodUp,
+                ToggleAnimation,
+                ToggleFrameStats,
+            ],
+            Mode::Cursor => &[
+                SelectUp,
+                SelectDown,
+                SelectFirst,
+                SelectLast,
+                Open,
+                Sort,
+                SortReverse,
+                NewAlert,
+                EditAlert,
+                DeleteAlert,
+                SnoozeAlert,
+                ToggleRepeat,
+            ],
+            Mode::Command => &[Submit, Cancel, Complete, SelectUp, SelectDown],
+        }
+    }
+
+    /// Where the bindings are looked up, the first mode wins
+    fn chain(&self) -> &'static [Mode] {
+        match self {
+            Mode::Normal => &[Mode::Normal],
+            Mode::Cursor => &[Mode::Cursor, Mode::Normal],
+            Mode::Command => &[Mode::Command],
+        }
+    }
+
+    /// The prefix of the mode in the config, `keys.cursor.` etc.
+    fn config_key(&self) -> &'static str {
+        match self {
+            Mode::Normal => "keys.",
+            Mode::Cursor => "keys.cursor.",
+            Mode::Command => "keys.command.",
+        }
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", format!("{self:?}").to_lowercase())
+    }
+}
+
+impl Display for Action {
+    /// The name that is used in the config, like `focus_next`
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = format!("{self:?}");
+        for (i, c) in name.chars().enumerate() {
+            if c.is_uppercase() && i > 0 {
+                write!(f, "_")?;
+            }
+            write!(f, "{}", c.to_ascii_lowercase())?;
+        }
+        Ok(())
+    }
+}
+
+/// A key together with its modifiers, written like `q`, `ctrl-c`, `shift-up` or `tab`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyBinding {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyBinding {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Self { code, modifiers }
+    }
+
+    pub const fn key(code: KeyCode) -> Self {
+        Self::new(code, KeyModifiers::NONE)
+    }
+
+    /// Shift is already part of the character, so `S` and `shift-s` are the same key
+    pub fn from_event(event: KeyEvent) -> Self {
+        let mut modifiers =
+            event.modifiers & (KeyModifiers::SHIFT | KeyModifiers::CONTROL | KeyModifiers::ALT);
+        if let KeyCode::Char(_) = event.code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        // terminals send backtab with shift
+        if event.code == KeyCode::BackTab {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self::new(event.code, modifiers)
+    }
+}
+
+impl FromStr for KeyBinding {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = s;
+        // a lone "-" is a key, not a separator
+        while let Some((m, r)) = rest.split_once('-').filter(|(_, r)| !r.is_empty()) {
+            modifiers |= match m.to_lowercase().as_str() {
+                "ctrl" => KeyModifiers::CONTROL,
+                "alt" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => bail!("Unknown modifier '{m}' in '{s}', expected ctrl, alt or shift"),
+            };
+            rest = r;
+        }
+
+        let code = match rest.to_lowercase().as_str() {
+            "esc" => KeyCode::Esc,
+            "enter" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "space" => KeyCode::Char(' '),
+            "backspace" => KeyCode::Backspace,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            f if f.len() > 1 && f.starts_with('f') && f[1..].parse::<u8>().is_ok() => {
+                KeyCode::F(f[1..].parse().unwrap_or(1))
+            }
+            _ => {
+                let mut chars = rest.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => KeyCode::Char(c),
+                    _ => bail!("Unknown key '{rest}'"),
+                }
+            }
+        };
+
+        // same as in from_event, shift only matters for keys that arent characters
+        if let KeyCode::Char(c) = code
+            && modifiers.contains(KeyModifiers::SHIFT)
+        {
+            modifiers.remove(KeyModifiers::SHIFT);
+            return Ok(Self::new(KeyCode::Char(c.to_ascii_uppercase()), modifiers));
+        }
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl Display for KeyBinding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (m, name) in [
+            (KeyModifiers::CONTROL, "ctrl-"),
+            (KeyModifiers::ALT, "alt-"),
+            (KeyModifiers::SHIFT, "shift-"),
+        ] {
+            if self.modifiers.contains(m) {
+                write!(f, "{name}")?;
+            }
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(c) => write!(f, "{c}"),
+            KeyCode::F(n) => write!(f, "f{n}"),
+            c => write!(f, "{}", format!("{c:?}").to_lowercase()),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for KeyBinding {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let raw = String::deserialize(deserializer)?;
+        raw.parse().map_err(|e| D::Error::custom(format!("{e}")))
+    }
+}
+
+/// Keys that are pressed after each other, written with spaces in between like `g h`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct KeySequence(pub Vec<KeyBinding>);
+
+impl FromStr for KeySequence {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let keys = s
+            .split_whitespace()
+            .map(KeyBinding::from_str)
+            .collect::<anyhow::Result<Vec<_>>>()?;
+        if keys.is_empty() {
+            bail!("Empty key binding");
+        }
+        Ok(Self(keys))
+    }
+}
+
+impl Display for KeySequence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, k) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{k}")?;
+        }
+        Ok(())
+    }
+}
+
+/// So the pressed keys can be looked up without building a sequence
+impl Borrow<[KeyBinding]> for KeySequence {
+    fn borrow(&self) -> &[KeyBinding] {
+        &self.0
+    }
+}
+
+/// One key or a list of keys for an action
+#[derive(Debug, Clone)]
+pub enum Keys {
+    One(KeySequence),
+    Many(Vec<KeySequence>),
+}
+
+impl<'de> Deserialize<'de> for Keys {
+    /// Parsed by hand, an untagged enum would swallow the error of the key that is wrong
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Raw {
+            One(String),
+            Many(Vec<String>),
+        }
+
+        let parse = |k: &str| {
+            k.parse::<KeySequence>()
+                .map_err(|e| D::Error::custom(format!("{e}")))
+        };
+        match Raw::deserialize(deserializer)? {
+            Raw::One(k) => Ok(Keys::One(parse(&k)?)),
+            Raw::Many(k) => Ok(Keys::Many(
+                k.iter().map(|k| parse(k)).collect::<Result<_, _>>()?,
+            )),
+        }
+    }
+}
+
+impl Keys {
+    fn into_vec(self) -> Vec<KeySequence> {
+        match self {
+            Keys::One(k) => vec![k],
+            Keys::Many(k) => k,
+        }
+    }
+}
+
+/// What the keys that were pressed so far mean
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lookup {
+    Action(Action),
+    /// They are the start of a sequence, wait for the next key
+    Pending,
+    None,
+}
+
+pub const PRESETS: [&str; 2] = ["default", "vim"];
+
+#[derive(Debug, Clone)]
+pub struct Keymap {
+    modes: HashMap<Mode, HashMap<KeySequence, Action>>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        use KeyCode::*;
+        let shift = |c| KeyBinding::new(c, KeyModifiers::SHIFT);
+        let key = KeyBinding::key;
+        let bind = |bindings: Vec<(KeyBinding, Action)>| {
+            bindings
+                .into_iter()
+                .map(|(k, a)| (KeySequence(vec![k]), a))
+                .collect::<HashMap<_, _>>()
+        };
+
+        Self {
+            modes: HashMap::from([
+                (
+                    Mode::Normal,
+                    bind(vec![
+                        (key(Esc), Action::Quit),
+                        (key(Char('q')), Action::Quit),
+                        (key(Char('?')), Action::Help),
+                        (key(Char(':')), Action::CommandLine),
+                        (key(Char('o')), Action::Overview),
+                        (key(Char('h')), Action::Heatmap),
+                        (key(Char('p')), Action::Portfolio),
+                        (key(Char('b')), Action::Ledger),
+                        (key(Char('m')), Action::CycleCostBasis),
+                        (key(Char('a')), Action::Alerts),
+                        (key(Char('x')), Action::Arbitrage),
+                        (key(Char('c')), Action::Comparison),
+                        (key(Char('/')), Action::AddProduct),
+                        (key(Char('w')), Action::NextWatchlist),
+                        (key(Up), Action::IncMult),
+                        (shift(Up), Action::IncMultFine),
+                        (key(Down), Action::DecMult),
+                        (shift(Down), Action::DecMultFine),
+                        (key(Tab), Action::FocusNext),
+                        (key(BackTab), Action::FocusPrev),
+                        (key(Char('z')), Action::ToggleMaximize),
+                        (key(Char('l')), Action::CycleLayout),
+                        (key(Char('1')), Action::ToggleSma),
+                        (key(Char('2')), Action::ToggleEma),
+                        (key(Char('3')), Action::ToggleVwap),
+                        (key(Char('4')), Action::ToggleBollinger),
+                        (key(Char('5')), Action::CycleOscillator),
+                        (key(Char('6')), Action::SelectIndicator),
+                        (key(Char('<')), Action::PeriodDown),
+                        (key(Char('>')), Action::PeriodUp),
+                        (key(Char('g')), Action::ToggleAnimation),
+                        (key(F(12)), Action::ToggleFrameStats),
+                    ]),
+                ),
+                (
+                    Mode::Cursor,
+                    bind(vec![
+                        (key(Up), Action::SelectUp),
+                        (key(Char('k')), Action::SelectUp),
+                        (key(Down), Action::SelectDown),
+                        (key(Char('j')), Action::SelectDown),
+                        (key(Home), Action::SelectFirst),
+                        (key(End), Action::SelectLast),
+                        (key(Enter), Action::Open),
+                        (key(Char('s')), Action::Sort),
+                        (key(Char('S')), Action::SortReverse),
+                        (key(Char('n')), Action::NewAlert),
+                        (key(Char('e')), Action::EditAlert),
+                        (key(Char('d')), Action::DeleteAlert),
+                        (key(Char('z')), Action::SnoozeAlert),
+                        (key(Char('r')), Action::ToggleRepeat),
+                    ]),
+                ),
+                (
+                    Mode::Command,
+                    bind(vec![
+                        (key(Enter), Action::Submit),
+                        (key(Esc), Action::Cancel),
+                        (key(Tab), Action::Complete),
+                        (key(Up), Action::SelectUp),
+                        (key(Down), Action::SelectDown),
+                    ]),
+                ),
+            ]),
+        }
+    }
+}
+
+impl Keymap {
+    /// One of the [`PRESETS`]
+    pub fn preset(name: &str) -> anyhow::Result<Self> {
+        let vim: &[(Mode, Action, &[&str])] = &[
+            (Mode::Normal, Action::Quit, &["q", "Z Z", "Z Q"]),
+            (Mode::Normal, Action::FocusNext, &["l", "tab", "ctrl-w w"]),
+            (
+                Mode::Normal,
+                Action::FocusPrev,
+                &["h", "backtab", "ctrl-w W"],
+            ),
+            (Mode::Normal, Action::IncMult, &["k", "up"]),
+            (Mode::Normal, Action::DecMult, &["j", "down"]),
+            (Mode::Normal, Action::IncMultFine, &["K", "shift-up"]),
+            (Mode::Normal, Action::DecMultFine, &["J", "shift-down"]),
+            (Mode::Normal, Action::ToggleMaximize, &["z z", "ctrl-w o"]),
+            (Mode::Normal, Action::CycleLayout, &["ctrl-w l"]),
+            (Mode::Normal, Action::Overview, &["g o"]),
+            (Mode::Normal, Action::Heatmap, &["g h"]),
+            (Mode::Normal, Action::Portfolio, &["g p"]),
+            (Mode::Normal, Action::Ledger, &["g b"]),
+            (Mode::Normal, Action::Alerts, &["g a"]),
+            (Mode::Normal, Action::Arbitrage, &["g x"]),
+            (Mode::Normal, Action::Comparison, &["g c"]),
+            (Mode::Normal, Action::NextWatchlist, &["g w"]),
+            (Mode::Normal, Action::ToggleAnimation, &["z a"]),
+            (Mode::Cursor, Action::SelectFirst, &["g g", "home"]),
+            (Mode::Cursor, Action::SelectLast, &["G", "end"]),
+            (Mode::Command, Action::SelectUp, &["up", "ctrl-p"]),
+            (Mode::Command, Action::SelectDown, &["down", "ctrl-n"]),
+        ];
+
+        let overrides = match name {
+            "default" => &[][..],
+            "vim" => vim,
+            _ => bail!(
+                "Unknown keymap preset '{name}', expected {}",
+                PRESETS.join(" or ")
+            ),
+        };
+        let mut map = Self::default();
+        for mode in Mode::ALL {
+            let keys = overrides
+                .iter()
+                .filter(|(m, _, _)| *m == mode)
+                .map(|(_, a, keys)| {
+                    let keys = keys
+                        .iter()
+                        .map(|k| k.parse())
+                        .collect::<anyhow::Result<_>>()?;
+                    Ok((*a, Keys::Many(keys)))
+                })
+                .collect::<anyhow::Result<HashMap<_, _>>>()?;
+            map.rebind(mode, &keys)?;
+        }
+        Ok(map)
+    }
+
+    /// The preset with the bindings from the config on top. An action from the config loses all
+    /// of its keys from the preset, so `quit = "x"` means q and esc dont quit anymore. Binding a
+    /// key that is still used by another action of the mode is an error, same as a key that is the
+    /// start of a sequence.
+    pub fn with_overrides(config: &KeysConfig) -> anyhow::Result<Self> {
+        let mut map = Self::preset(config.preset.as_deref().unwrap_or("default"))?;
+        map.rebind(Mode::Normal, &config.normal)?;
+        map.rebind(Mode::Cursor, &config.cursor)?;
+        map.rebind(Mode::Command, &config.command)?;
+        Ok(map)
+    }
+
+    fn rebind(&mut self, mode: Mode, overrides: &HashMap<Action, Keys>) -> anyhow::Result<()> {
+        let prefix = mode.config_key();
+        let bindings = self.modes.entry(mode).or_default();
+        bindings.retain(|_, a| !overrides.contains_key(a));
+
+        // sorted, so the error is the same on every run
+        let mut overrides = overrides.iter().collect::<Vec<_>>();
+        overrides.sort_by_key(|(a, _)| a.to_string());
+        for (action, keys) in overrides {
+            if !mode.actions().contains(action) {
+                bail!("{prefix}{action}: {action} does nothing in {mode} mode");
+            }
+            for k in keys.clone().into_vec() {
+                if let Some(other) = bindings.get(&k).filter(|o| *o != action) {
+                    bail!("{prefix}{action}: '{k}' is already bound to {other}");
+                }
+                let clash = bindings.iter().find(|(other, _)| {
+                    let (short, long) = match other.0.len() < k.0.len() {
+                        true => (&other.0, &k.0),
+                        false => (&k.0, &other.0),
+                    };
+                    short.len() != long.len() && long.starts_with(short)
+                });
+                if let Some((other, a)) = clash {
+                    bail!(
+                        "{prefix}{action}: '{k}' clashes with '{other}' of {a}, one starts the other"
+                    );
+                }
+                bindings.insert(k, *action);
+            }
+        }
+        Ok(())
+    }
+
+    /// What the keys that were pressed after each other do in the mode
+    pub fn lookup(&self, mode: Mode, keys: &[KeyBinding]) -> Lookup {
+        for m in mode.chain() {
+            let Some(bindings) = self.modes.get(m) else {
+                continue;
+            };
+            if let Some(a) = bindings.get(keys) {
+                return Lookup::Action(*a);
+            }
+            if bindings
+                .keys()
+                .any(|s| s.0.len() > keys.len() && s.0.starts_with(keys))
+            {
+                return Lookup::Pending;
+            }
+        }
+        Lookup::None
+    }
+
+    /// The keys of every action of the mode, in the order of [`Mode::actions`]
+    pub fn bindings(&self, mode: Mode) -> Vec<(Action, Vec<&KeySequence>)> {
+        let bindings = self.modes.get(&mode);
+        mode.actions()
+            .iter()
+            .map(|action| {
+                let mut keys = bindings
+                    .iter()
+                    .flat_map(|b| b.iter())
+                    .filter(|(_, a)| *a == action)
+                    .map(|(k, _)| k)
+                    .collect::<Vec<_>>();
+                keys.sort_by_key(|k| (k.0.len(), k.to_string().len(), k.to_string()));
+                (*action, keys)
+            })
+            .collect()
+    }
+
+    /// A short hint like `n new, e edit` with the first key of every action, unbound ones are
+    /// left out
+    pub fn hint(&self, mode: Mode, actions: &[(Action, &str)]) -> String {
+        let bindings = self.bindings(mode);
+        actions
+            .iter()
+            .filter_map(|(action, label)| {
+                let (_, keys) = bindings.iter().find(|(a, _)| a == action)?;
+                Some(format!("{} {label}", keys.first()?))
+            })
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
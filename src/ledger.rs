@@ -0,0 +1,727 @@
+//! Transaction ledger
+//!
+//! Transactions get imported from exchange CSV exports into a local ledger file. From that ledger
+//! the cost basis of every asset is calculated with FIFO, LIFO or average cost, and the realized
+//! and unrealized P&L are valued with the live prices of the ticker feed.
+
+use std::{
+    collections::{HashMap, VecDeque},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, bail};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use clap::ValueEnum;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Cell, Row, Table},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    currency::rate, products::Catalog, sockets::last_message, theme::Theme, utils::data_dir,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TxKind {
+    Buy,
+    Sell,
+    /// Coins that came in from somewhere else, they keep the spot price as cost
+    Receive,
+    /// Coins that went out to somewhere else, they leave without realizing anything
+    Send,
+    /// Rewards, staking and the like, the spot price at that time is the cost
+    Income,
+}
+
+impl TxKind {
+    /// Maps the transaction type of an export to our kinds, `None` for the ones we dont care about
+    fn parse(v: &str) -> Option<Self> {
+        let v = v.to_lowercase();
+        let kind = match v.as_str() {
+            _ if v.contains("sell") || v.contains("convert") => TxKind::Sell,
+            _ if v.contains("buy") => TxKind::Buy,
+            _ if v.contains("receive") || v.contains("deposit") => TxKind::Receive,
+            _ if v.contains("send") || v.contains("withdraw") => TxKind::Send,
+            _ if [
+                "reward", "staking", "income", "learning", "airdrop", "interest",
+            ]
+            .iter()
+            .any(|k| v.contains(k)) =>
+            {
+                TxKind::Income
+            }
+            _ => return None,
+        };
+        Some(kind)
+    }
+
+    fn is_acquisition(&self) -> bool {
+        matches!(self, TxKind::Buy | TxKind::Receive | TxKind::Income)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transaction {
+    /// Id from the export, or one built from the other fields if the export doesnt have any
+    pub id: String,
+    /// Unix timestamp in ms
+    pub time: i64,
+    pub kind: TxKind,
+    pub asset: String,
+    pub quantity: f64,
+    /// Price per unit in `currency`
+    pub price: f64,
+    pub currency: String,
+    pub fee: f64,
+}
+
+/// Which columns of the CSV hold which value, by their header name. Multiple names can be given
+/// separated by `|`, the first one that exists gets used.
+#[derive(Debug, Clone)]
+pub struct ColumnMap {
+    pub id: Option<String>,
+    pub timestamp: String,
+    pub kind: String,
+    pub asset: String,
+    pub quantity: String,
+    pub price: String,
+    pub currency: Option<String>,
+    pub fee: Option<String>,
+    /// Used to find the second half of convert transactions
+    pub notes: Option<String>,
+}
+
+impl ColumnMap {
+    /// The transaction history export of Coinbase
+    pub fn coinbase() -> Self {
+        Self {
+            id: Some("ID".into()),
+            timestamp: "Timestamp".into(),
+            kind: "Transaction Type".into(),
+            asset: "Asset".into(),
+            quantity: "Quantity Transacted".into(),
+            // older exports call it spot price
+            price: "Price at Transaction|Spot Price at Transaction".into(),
+            currency: Some("Price Currency|Spot Price Currency".into()),
+            fee: Some("Fees and/or Spread".into()),
+            notes: Some("Notes".into()),
+        }
+    }
+
+    /// Parses a mapping like `timestamp=Date,kind=Side,asset=Coin,quantity=Amount,price=Price`.
+    /// `id`, `currency`, `fee` and `notes` are optional.
+    pub fn parse(v: &str) -> anyhow::Result<Self> {
+        let pairs = v
+            .split(',')
+            .map(|p| {
+                p.split_once('=')
+                    .map(|(k, v)| (k.trim().to_lowercase(), v.trim().to_string()))
+                    .with_context(|| format!("Expected key=column but got '{p}'"))
+            })
+            .collect::<anyhow::Result<HashMap<String, String>>>()?;
+
+        for key in pairs.keys() {
+            if ![
+                "id",
+                "timestamp",
+                "kind",
+                "asset",
+                "quantity",
+                "price",
+                "currency",
+                "fee",
+                "notes",
+            ]
+            .contains(&key.as_str())
+            {
+                bail!("Unknown column key '{key}'");
+            }
+        }
+
+        let required = |k: &str| {
+            pairs
+                .get(k)
+                .cloned()
+                .with_context(|| format!("Column mapping is missing '{k}'"))
+        };
+
+        Ok(Self {
+            id: pairs.get("id").cloned(),
+            timestamp: required("timestamp")?,
+            kind: required("kind")?,
+            asset: required("asset")?,
+            quantity: required("quantity")?,
+            price: required("price")?,
+            currency: pairs.get("currency").cloned(),
+            fee: pairs.get("fee").cloned(),
+            notes: pairs.get("notes").cloned(),
+        })
+    }
+}
+
+/// Parses numbers like `$1,234.56`, `-0.5` or `1.5E-5`, only currency symbols, whitespace and
+/// thousands separators get dropped
+fn parse_number(v: &str) -> Option<f64> {
+    let cleaned = v
+        .chars()
+        .filter(|c| !c.is_whitespace() && !matches!(c, ',' | '$' | '€' | '£' | '¥' | '₿'))
+        .collect::<String>();
+    cleaned.parse::<f64>().ok().filter(|n| n.is_finite())
+}
+
+fn parse_time(v: &str) -> Option<i64> {
+    let v = v.trim();
+    if let Ok(t) = DateTime::parse_from_rfc3339(v) {
+        return Some(t.timestamp_millis());
+    }
+
+    let naive = v.trim_end_matches(" UTC").trim_end_matches('Z');
+    for fmt in ["%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M:%S", "%Y-%m-%d %H:%M"] {
+        if let Ok(t) = NaiveDateTime::parse_from_str(naive, fmt) {
+            return Some(t.and_utc().timestamp_millis());
+        }
+    }
+
+    NaiveDate::parse_from_str(naive, "%Y-%m-%d")
+        .ok()
+        .and_then(|d| d.and_hms_opt(0, 0, 0))
+        .map(|t| t.and_utc().timestamp_millis())
+}
+
+/// Finds the bought side of a Coinbase convert note like `Converted 0.01 BTC to 0.15 ETH`
+fn parse_convert_note(note: &str) -> Option<(f64, String)> {
+    let (_, to) = note.split_once(" to ")?;
+    let mut parts = to.split_whitespace();
+    let quantity = parse_number(parts.next()?)?;
+    let asset = parts.next()?.to_string();
+    Some((quantity, asset))
+}
+
+/// Reads every transaction of a CSV export. Lines in front of the header (Coinbase puts a few of
+/// those in there) get skipped.
+pub fn import_csv(path: &Path, map: &ColumnMap) -> anyhow::Result<Vec<Transaction>> {
+    let raw = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let header_start = raw
+        .lines()
+        .position(|l| {
+            l.split(',').any(|c| {
+                map.timestamp
+                    .split('|')
+                    .any(|name| c.trim().trim_matches('"') == name)
+            })
+        })
+        .with_context(|| format!("Couldnt find a header with a '{}' column", map.timestamp))?;
+    let body = raw
+        .lines()
+        .skip(header_start)
+        .collect::<Vec<&str>>()
+        .join("\n");
+
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .from_reader(body.as_bytes());
+    let headers = reader.headers()?.clone();
+    let column = |names: &str| {
+        names
+            .split('|')
+            .find_map(|name| headers.iter().position(|h| h.trim() == name))
+    };
+    let required = |name: &str| column(name).with_context(|| format!("Missing column '{name}'"));
+
+    let timestamp = required(&map.timestamp)?;
+    let kind = required(&map.kind)?;
+    let asset = required(&map.asset)?;
+    let quantity = required(&map.quantity)?;
+    let price = required(&map.price)?;
+    let id = map.id.as_deref().and_then(column);
+    let currency = map.currency.as_deref().and_then(column);
+    let fee = map.fee.as_deref().and_then(column);
+    let notes = map.notes.as_deref().and_then(column);
+
+    let mut txs = vec![];
+    for (line, record) in reader.records().enumerate() {
+        let record = record?;
+        let get = |i: usize| record.get(i).unwrap_or_default().trim();
+        let row = line + header_start + 2;
+
+        let Some(tx_kind) = TxKind::parse(get(kind)) else {
+            continue;
+        };
+        let time = parse_time(get(timestamp))
+            .with_context(|| format!("Invalid timestamp in row {row}"))?;
+        let qty = parse_number(get(quantity))
+            .with_context(|| format!("Invalid quantity in row {row}"))?
+            .abs();
+        let unit_price = parse_number(get(price)).unwrap_or(0.0);
+        let tx_currency = currency.map(get).unwrap_or("USD").to_uppercase();
+        let tx_fee = fee.and_then(|f| parse_number(get(f))).unwrap_or(0.0).abs();
+        let tx_asset = get(asset).to_uppercase();
+        let tx_id = id
+            .map(get)
+            .filter(|i| !i.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| format!("{time}-{tx_asset}-{qty}-{:?}", tx_kind));
+
+        txs.push(Transaction {
+            id: tx_id.clone(),
+            time,
+            kind: tx_kind,
+            asset: tx_asset,
+            quantity: qty,
+            price: unit_price,
+            currency: tx_currency.clone(),
+            fee: tx_fee,
+        });
+
+        // a convert is a sell of one asset and a buy of another one, but coinbase only exports
+        // the sell side and hides the other one in the notes
+        if get(kind).to_lowercase().contains("convert")
+            && let Some((to_qty, to_asset)) = notes.and_then(|n| parse_convert_note(get(n)))
+            && to_qty > 0.0
+        {
+            txs.push(Transaction {
+                id: format!("{tx_id}-to"),
+                time,
+                kind: TxKind::Buy,
+                asset: to_asset.to_uppercase(),
+                quantity: to_qty,
+                price: qty * unit_price / to_qty,
+                currency: tx_currency,
+                fee: 0.0,
+            });
+        }
+    }
+
+    Ok(txs)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum CostBasisMethod {
+    /// First in, first out
+    #[default]
+    Fifo,
+    /// Last in, first out
+    Lifo,
+    /// Every unit costs the average of all units
+    Average,
+}
+
+impl CostBasisMethod {
+    pub fn cycle(self) -> Self {
+        match self {
+            CostBasisMethod::Fifo => CostBasisMethod::Lifo,
+            CostBasisMethod::Lifo => CostBasisMethod::Average,
+            CostBasisMethod::Average => CostBasisMethod::Fifo,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            CostBasisMethod::Fifo => "FIFO",
+            CostBasisMethod::Lifo => "LIFO",
+            CostBasisMethod::Average => "Average",
+        }
+    }
+}
+
+/// A bunch of units that got acquired for the same price per unit
+#[derive(Debug, Clone, Copy)]
+struct Lot {
+    quantity: f64,
+    unit_cost: f64,
+}
+
+/// Cost basis and P&L of a single asset
+#[derive(Debug, Clone, Default)]
+pub struct AssetPnl {
+    pub asset: String,
+    /// The currency the cost basis is in
+    pub currency: String,
+    pub quantity: f64,
+    /// What the units that are still held did cost
+    pub cost_basis: f64,
+    pub realized: f64,
+}
+
+impl AssetPnl {
+    /// The unrealized P&L at the given price, `rate` converts the cost basis into the currency
+    /// of the price
+    pub fn unrealized(&self, price: f64, rate: f64) -> f64 {
+        self.quantity * price - self.cost_basis * rate
+    }
+}
+
+/// Takes `quantity` units out of the lots and returns what they did cost
+fn take_from_lots(lots: &mut VecDeque<Lot>, mut quantity: f64, method: CostBasisMethod) -> f64 {
+    let mut cost = 0.0;
+
+    while quantity > f64::EPSILON {
+        let lot = match method {
+            CostBasisMethod::Lifo => lots.back_mut(),
+            // average cost only ever has a single pooled lot, so front works for both
+            CostBasisMethod::Fifo | CostBasisMethod::Average => lots.front_mut(),
+        };
+        // selling more than we know of, the rest has no known cost
+        let Some(lot) = lot else {
+            break;
+        };
+
+        let taken = lot.quantity.min(quantity);
+        cost += taken * lot.unit_cost;
+        lot.quantity -= taken;
+        quantity -= taken;
+
+        if lot.quantity <= f64::EPSILON {
+            match method {
+                CostBasisMethod::Lifo => lots.pop_back(),
+                CostBasisMethod::Fifo | CostBasisMethod::Average => lots.pop_front(),
+            };
+        }
+    }
+
+    cost
+}
+
+/// Goes through the transactions in order and calculates the cost basis and realized P&L of
+/// every asset
+pub fn cost_basis(transactions: &[Transaction], method: CostBasisMethod) -> Vec<AssetPnl> {
+    let mut txs = transactions.iter().collect::<Vec<&Transaction>>();
+    txs.sort_by_key(|t| t.time);
+
+    let mut lots: HashMap<String, VecDeque<Lot>> = HashMap::new();
+    let mut pnl: HashMap<String, AssetPnl> = HashMap::new();
+
+    for tx in txs {
+        let asset_lots = lots.entry(tx.asset.clone()).or_default();
+        let entry = pnl.entry(tx.asset.clone()).or_insert_with(|| AssetPnl {
+            asset: tx.asset.clone(),
+            currency: tx.currency.clone(),
+            ..Default::default()
+        });
+
+        if tx.kind.is_acquisition() {
+            // fees are part of what the units did cost
+            let unit_cost = if tx.quantity > 0.0 {
+                (tx.quantity * tx.price + tx.fee) / tx.quantity
+            } else {
+                0.0
+            };
+            asset_lots.push_back(Lot {
+                quantity: tx.quantity,
+                unit_cost,
+            });
+
+            if method == CostBasisMethod::Average {
+                let quantity = asset_lots.iter().map(|l| l.quantity).sum::<f64>();
+                let cost = asset_lots
+                    .iter()
+                    .map(|l| l.quantity * l.unit_cost)
+                    .sum::<f64>();
+                asset_lots.clear();
+                asset_lots.push_back(Lot {
+                    quantity,
+                    unit_cost: if quantity > 0.0 { cost / quantity } else { 0.0 },
+                });
+            }
+            continue;
+        }
+
+        let cost = take_from_lots(asset_lots, tx.quantity, method);
+        if tx.kind == TxKind::Sell {
+            entry.realized += tx.quantity * tx.price - tx.fee - cost;
+        }
+    }
+
+    let mut out = pnl
+        .into_values()
+        .map(|mut p| {
+            let asset_lots = lots.get(&p.asset).cloned().unwrap_or_default();
+            p.quantity = asset_lots.iter().map(|l| l.quantity).sum();
+            p.cost_basis = asset_lots.iter().map(|l| l.quantity * l.unit_cost).sum();
+            p
+        })
+        .collect::<Vec<AssetPnl>>();
+    out.sort_by(|a, b| a.asset.cmp(&b.asset));
+    out
+}
+
+/// Every imported transaction, stored as json in the data directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Ledger {
+    pub transactions: Vec<Transaction>,
+}
+
+impl Ledger {
+    pub fn default_path() -> PathBuf {
+        data_dir().join("ledger.json")
+    }
+
+    /// Loads the ledger, a missing file is just an empty ledger
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&raw).with_context(|| format!("Invalid ledger in {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Adds the transactions that arent in the ledger yet and returns how many got added, so the
+    /// same export can be imported multiple times
+    pub fn add(&mut self, transactions: Vec<Transaction>) -> usize {
+        let before = self.transactions.len();
+        for tx in transactions {
+            if !self.transactions.iter().any(|t| t.id == tx.id) {
+                self.transactions.push(tx);
+            }
+        }
+        self.transactions.len() - before
+    }
+
+    /// The products that are needed to value the held assets in `quote`, only the ones the
+    /// exchange lists, the feed rejects the whole subscription for a single unknown one
+    pub fn products(&self, quote: &str, catalog: &Catalog) -> Vec<String> {
+        let mut products = self
+            .transactions
+            .iter()
+            .filter(|t| t.asset != quote)
+            .map(|t| format!("{}-{quote}", t.asset))
+            .filter(|p| catalog.accepts(p))
+            .collect::<Vec<String>>();
+        products.sort();
+        products.dedup();
+        products
+    }
+}
+
+pub fn render_ledger(
+    frame: &mut Frame,
+    area: Rect,
+    ledger: &Ledger,
+    method: CostBasisMethod,
+    quote: &str,
+    theme: &Theme,
+) {
+    let pnl = cost_basis(&ledger.transactions, method);
+
+    let pnl_color = |v: f64| {
+        if v >= 0.0 { theme.up } else { theme.down }
+    };
+    let num = |v: String| Cell::from(Line::from(v).right_aligned());
+
+    let header = Row::new(
+        [
+            "Asset",
+            "Quantity",
+            "Avg Cost",
+            "Cost Basis",
+            "Price",
+            "Value",
+            "Unrealized",
+            "Realized",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, h)| match i {
+            0 => Cell::from(*h),
+            _ => num(h.to_string()),
+        }),
+    )
+    .style(Style::new().bold().fg(theme.label));
+
+    let (mut total_unrealized, mut total_realized) = (0.0, 0.0);
+    let mut rows = pnl
+        .iter()
+        .map(|p| {
+            let price = if p.asset == quote {
+                Some(1.0)
+            } else {
+                last_message(&format!("{}-{quote}", p.asset))
+                    .and_then(|m| m.price.parse::<f64>().ok())
+            };
+            let avg = if p.quantity > 0.0 {
+                p.cost_basis / p.quantity
+            } else {
+                0.0
+            };
+            // the cost basis is in the currency of the transactions, not necessarily the quote
+            let to_quote = rate(&p.currency, quote);
+            let unrealized = price.zip(to_quote).map(|(pr, r)| p.unrealized(pr, r));
+            let realized = to_quote.map(|r| p.realized * r);
+            total_unrealized += unrealized.unwrap_or(0.0);
+            total_realized += realized.unwrap_or(0.0);
+
+            Row::new(vec![
+                Cell::from(p.asset.clone()),
+                num(format!("{:.8}", p.quantity)),
+                num(format!("{avg:.2}")),
+                num(format!("{:.2} {}", p.cost_basis, p.currency)),
+                num(price.map(|v| format!("{v:.2}")).unwrap_or("-".into())),
+                num(price
+                    .map(|v| format!("{:.2}", v * p.quantity))
+                    .unwrap_or("-".into())),
+                num(unrealized.map(|v| format!("{v:+.2}")).unwrap_or("-".into()))
+                    .fg(pnl_color(unrealized.unwrap_or(0.0))),
+                num(realized.map(|v| format!("{v:+.2}")).unwrap_or("-".into()))
+                    .fg(pnl_color(realized.unwrap_or(0.0))),
+            ])
+            .style(Style::new().fg(theme.label))
+        })
+        .collect::<Vec<Row>>();
+
+    rows.push(
+        Row::new(vec![
+            Cell::from("Total"),
+            Cell::from(""),
+            Cell::from(""),
+            Cell::from(""),
+            Cell::from(""),
+            Cell::from(""),
+            num(format!("{total_unrealized:+.2}")).fg(pnl_color(total_unrealized)),
+            num(format!("{total_realized:+.2}")).fg(pnl_color(total_realized)),
+        ])
+        .style(Style::new().bold().fg(theme.label))
+        .top_margin(1),
+    );
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(16),
+            Constraint::Length(12),
+            Constraint::Length(18),
+            Constraint::Length(12),
+            Constraint::Length(14),
+            Constraint::Length(14),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(header);
+
+    frame.render_widget(
+        theme.panel(table).title(format!(
+            "Ledger - {} transactions - {} in {quote}",
+            ledger.transactions.len(),
+            method.label()
+        )),
+        area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(time: i64, kind: TxKind, quantity: f64, price: f64) -> Transaction {
+        Transaction {
+            id: time.to_string(),
+            time,
+            kind,
+            asset: "BTC".into(),
+            quantity,
+            price,
+            currency: "USD".into(),
+            fee: 0.0,
+        }
+    }
+
+    fn lots() -> VecDeque<Lot> {
+        VecDeque::from([
+            Lot {
+                quantity: 1.0,
+                unit_cost: 100.0,
+            },
+            Lot {
+                quantity: 1.0,
+                unit_cost: 200.0,
+            },
+        ])
+    }
+
+    #[test]
+    fn parses_numbers() {
+        assert_eq!(parse_number("$1,234.56"), Some(1234.56));
+        assert_eq!(parse_number("-0.5"), Some(-0.5));
+        assert_eq!(parse_number(" € 12 "), Some(12.0));
+        assert_eq!(parse_number("1e5"), Some(100000.0));
+        assert_eq!(parse_number("1.5E-5"), Some(0.000015));
+        assert_eq!(parse_number(""), None);
+        assert_eq!(parse_number("12abc"), None);
+        assert_eq!(parse_number("inf"), None);
+    }
+
+    #[test]
+    fn takes_oldest_lots_first_with_fifo() {
+        let mut l = lots();
+        assert_eq!(take_from_lots(&mut l, 1.5, CostBasisMethod::Fifo), 200.0);
+        assert_eq!(l.len(), 1);
+        assert_eq!(l[0].quantity, 0.5);
+        assert_eq!(l[0].unit_cost, 200.0);
+    }
+
+    #[test]
+    fn takes_newest_lots_first_with_lifo() {
+        let mut l = lots();
+        assert_eq!(take_from_lots(&mut l, 1.5, CostBasisMethod::Lifo), 250.0);
+        assert_eq!(l.len(), 1);
+        assert_eq!(l[0].quantity, 0.5);
+        assert_eq!(l[0].unit_cost, 100.0);
+    }
+
+    #[test]
+    fn takes_the_pooled_lot_with_average() {
+        let mut l = VecDeque::from([Lot {
+            quantity: 2.0,
+            unit_cost: 150.0,
+        }]);
+        assert_eq!(take_from_lots(&mut l, 0.5, CostBasisMethod::Average), 75.0);
+        assert_eq!(l[0].quantity, 1.5);
+    }
+
+    #[test]
+    fn selling_more_than_held_only_costs_what_is_known() {
+        let mut l = lots();
+        assert_eq!(take_from_lots(&mut l, 5.0, CostBasisMethod::Fifo), 300.0);
+        assert!(l.is_empty());
+    }
+
+    #[test]
+    fn realizes_pnl_of_a_partial_sell() {
+        let txs = [
+            tx(1, TxKind::Buy, 1.0, 100.0),
+            tx(2, TxKind::Buy, 1.0, 200.0),
+            tx(3, TxKind::Sell, 1.5, 300.0),
+        ];
+        let expected = [
+            // sells 1 at 100 and 0.5 at 200
+            (CostBasisMethod::Fifo, 250.0, 100.0),
+            // sells 1 at 200 and 0.5 at 100
+            (CostBasisMethod::Lifo, 200.0, 50.0),
+            // sells 1.5 at 150
+            (CostBasisMethod::Average, 225.0, 75.0),
+        ];
+        for (method, realized, basis) in expected {
+            let pnl = cost_basis(&txs, method);
+            assert_eq!(pnl.len(), 1);
+            assert_eq!(pnl[0].quantity, 0.5, "{method:?}");
+            assert_eq!(pnl[0].realized, realized, "{method:?}");
+            assert_eq!(pnl[0].cost_basis, basis, "{method:?}");
+        }
+    }
+}
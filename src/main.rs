@@ -1,10 +1,16 @@
 use clap::Parser;
 
 use crate::{
+    alerts::AlertEngine,
     app::App,
-    events::EventHandler,
+    commands::CommandLine,
+    config::{Config, ConfigWatch, Settings},
+    ledger::{ColumnMap, Ledger, import_csv},
     opts::CliOpts,
-    sockets::{BaseSocket, WsMessage},
+    portfolio::Portfolio,
+    products::Catalog,
+    sockets::BaseSocket,
+    toast::Toast,
 };
 
 mod opts;
@@ -13,10 +19,32 @@ mod tui;
 mod utils;
 
 pub mod macros;
+pub mod alerts;
 pub mod app;
+pub mod arbitrage;
+pub mod commands;
+pub mod comparison;
+pub mod config;
+pub mod currency;
+pub mod delivery;
 pub mod events;
+pub mod frame_stats;
+pub mod heatmap;
+pub mod help;
+pub mod indicators;
+pub mod keymap;
+pub mod ledger;
 pub mod ui;
 pub mod memes;
+pub mod oscillators;
+pub mod overview;
+pub mod portfolio;
+pub mod products;
+pub mod pressure;
+pub mod prompt;
+pub mod terminal;
+pub mod theme;
+pub mod toast;
 
 
 /// Widgets
@@ -27,13 +55,112 @@ async fn main() -> color_eyre::Result<()> {
     let opts = CliOpts::parse();
     color_eyre::install()?;
 
-    tokio::spawn(BaseSocket::connect(opts.watching));
+    let ledger_path = Ledger::default_path();
+    let mut ledger = Ledger::load(&ledger_path).map_err(|e| color_eyre::eyre::eyre!("{e:#}"))?;
+
+    if let Some(path) = &opts.import {
+        let map = match &opts.columns {
+            Some(c) => ColumnMap::parse(c),
+            None => Ok(ColumnMap::coinbase()),
+        }
+        .map_err(|e| color_eyre::eyre::eyre!("{e:#}"))?;
+
+        let txs = import_csv(path, &map).map_err(|e| color_eyre::eyre::eyre!("{e:#}"))?;
+        let found = txs.len();
+        let added = ledger.add(txs);
+        ledger
+            .save(&ledger_path)
+            .map_err(|e| color_eyre::eyre::eyre!("{e:#}"))?;
+
+        println!(
+            "Imported {added} new of {found} transactions into {}",
+            ledger_path.display()
+        );
+        return Ok(());
+    }
+
+    let portfolio = Portfolio::load(&opts.portfolio.clone().unwrap_or(Portfolio::default_path()))
+        .map_err(|e| color_eyre::eyre::eyre!("{e:#}"))?;
+
+    let alerts = AlertEngine::load(&AlertEngine::default_path())
+        .map_err(|e| color_eyre::eyre::eyre!("{e:#}"))?;
+
+    // without the product list nothing can be validated, but the feed still works
+    let catalog = Catalog::load(&opts.api_url).await.unwrap_or_else(|e| {
+        eprintln!("Couldnt load the product list, skipping validation: {e:#}");
+        Catalog::default()
+    });
+    let config_path = opts.config.clone().unwrap_or(Config::default_path());
+    let config = Config::load(&config_path)
+        .map_err(|e| color_eyre::eyre::eyre!("{e:#}"))?;
+    let mut settings =
+        Settings::resolve(&config, &opts).map_err(|e| color_eyre::eyre::eyre!("{e:#}"))?;
+    settings.watching = catalog
+        .validate(&settings.watching)
+        .map_err(|e| color_eyre::eyre::eyre!("{e:#}"))?;
+    for products in settings.watchlists.values_mut() {
+        *products = catalog
+            .validate(products)
+            .map_err(|e| color_eyre::eyre::eyre!("{e:#}"))?;
+    }
+
+    // the holdings and alerts need their prices too, even if they dont get a chart
+    let mut pinned: Vec<String> = vec![];
+    for p in portfolio
+        .products()
+        .into_iter()
+        .chain(ledger.products(&portfolio.quote, &catalog))
+        .chain(alerts.products())
+        .chain(opts.rate_products.clone())
+    {
+        if !pinned.contains(&p) {
+            pinned.push(p);
+        }
+    }
+    // a single unknown product makes the exchange reject the whole subscription
+    let (pinned, unknown) = catalog.filter(&pinned);
+    let mut products = settings.watching.clone();
+    for p in pinned.iter() {
+        if !products.contains(p) {
+            products.push(p.clone());
+        }
+    }
+    let (feed_tx, feed_rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(BaseSocket::connect(
+        products,
+        settings.messages_per_product,
+        feed_rx,
+    ));
 
     let opts = CliOpts::parse();
 
     let term = ratatui::init();
 
-    let app = App::new(Some(opts.watching));
+    let mut app = App::new(settings);
+    match ConfigWatch::new(config_path, opts.clone(), app.events.sender()) {
+        Ok(w) => app.config_watch = Some(w),
+        Err(e) => {
+            app.toast = Some(Toast::error(format!(
+                "Config changes wont be picked up: {e:#}"
+            )))
+        }
+    }
+    if !unknown.is_empty() {
+        app.toast = Some(Toast::error(format!(
+            "Not subscribed:\n{}",
+            unknown.join("\n")
+        )));
+    }
+    app.portfolio = portfolio;
+    app.ledger = ledger;
+    app.cost_basis = opts.cost_basis;
+    app.alerts = alerts;
+    app.commands = CommandLine::load(&CommandLine::default_path());
+    app.display_currency = opts.display_currency.map(|c| c.to_uppercase());
+    app.catalog = catalog;
+    app.feed = Some(feed_tx);
+    app.pinned_products = pinned;
+    app.arbitrage.threshold = opts.arb_threshold;
 
     let res = app.run(term).await;
 
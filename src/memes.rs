@@ -1,3 +1,36 @@
+//! Memes
+//!
+//! The header and footer text. Meme packs are toml files with pairs of header and footer lines for
+//! three moods: `neutral`, `moon` when the focused coin went up more than the mood threshold over
+//! the chart timeframe and `pain` when it went down as much. `stonks` is bundled, own packs go into
+//! `memes/<name>.toml` in the config directory. A new line gets picked on a timer and when the mood
+//! changes.
+//!
+//! ```toml
+//! neutral = [["me when the stonks go brr", "bottom text."]]
+//! moon = [["{coin} to the moon", "{change}, wen lambo"]]
+//! pain = [["{coin} {change}", "this is fine"]]
+//! ```
+//!
+//! `{coin}` is the base asset like BTC, `{product}` the product, `{change}` the change like
+//! +2.31% and `{price}` the last price.
+
+use anyhow::bail;
+use serde::Deserialize;
+
+use crate::{sockets::window_prices, utils::load_named_toml};
+
+pub const DEFAULT_PACK: &str = "stonks";
+const BUNDLED: [(&str, &str); 1] = [("stonks", include_str!("../memes/stonks.toml"))];
+pub const DEFAULT_ROTATE_SECS: u64 = 60;
+/// Percent change of the focused coin from which on the mood isnt neutral anymore
+pub const DEFAULT_MOOD_THRESHOLD: f64 = 2.0;
+/// Share of the threshold the change has to fall back by before the mood ends, so a coin hovering
+/// at the threshold doesnt swap the lines all the time
+const MOOD_MARGIN: f64 = 0.25;
+const PLACEHOLDERS: [&str; 4] = ["{coin}", "{product}", "{change}", "{price}"];
+
+#[derive(Debug, Clone)]
 pub struct XorShift32 {
     state: u64,
 }
@@ -7,7 +40,7 @@ impl XorShift32 {
         XorShift32 { state: seed }
     }
 
-    pub fn next(&mut self) -> u64 {
+    pub fn next_u64(&mut self) -> u64 {
         let mut x = self.state;
         x ^= x << 13;
         x ^= x >> 17;
@@ -17,11 +50,199 @@ impl XorShift32 {
     }
 
     pub fn gen_range(&mut self, n: usize) -> usize {
-        self.next() as usize % n // returns 1..=N
+        self.next_u64() as usize % n // returns 1..=N
+    }
+}
+
+/// How the focused coin is doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mood {
+    #[default]
+    Neutral,
+    Moon,
+    Pain,
+}
+
+/// Header and footer
+type Meme = (String, String);
+
+/// The lines of one or more packs
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Memes {
+    #[serde(default)]
+    neutral: Vec<Meme>,
+    #[serde(default)]
+    moon: Vec<Meme>,
+    #[serde(default)]
+    pain: Vec<Meme>,
+}
+
+impl Memes {
+    /// Loads the packs by name or path and mixes their lines, own packs in the config directory
+    /// win over the bundled ones
+    pub fn load(names: &[String]) -> anyhow::Result<Self> {
+        let mut memes = Self::default();
+        for name in names {
+            let pack = Self::load_pack(name)?;
+            memes.neutral.extend(pack.neutral);
+            memes.moon.extend(pack.moon);
+            memes.pain.extend(pack.pain);
+        }
+        if memes.neutral.is_empty() {
+            bail!("The meme packs need at least one neutral line");
+        }
+        Ok(memes)
+    }
+
+    fn load_pack(name: &str) -> anyhow::Result<Self> {
+        load_named_toml("meme pack", "memes", name, &BUNDLED, false, Self::parse)
+    }
+
+    fn parse(raw: &str) -> anyhow::Result<Self> {
+        let memes: Self = toml::from_str(raw)?;
+        for (mood, lines) in [
+            ("neutral", &memes.neutral),
+            ("moon", &memes.moon),
+            ("pain", &memes.pain),
+        ] {
+            for line in lines.iter().flat_map(|(top, bottom)| [top, bottom]) {
+                if let Some(p) = unknown_placeholder(line) {
+                    bail!(
+                        "{mood}: unknown placeholder {p} in '{line}', expected {}",
+                        PLACEHOLDERS.join(", ")
+                    );
+                }
+            }
+        }
+        Ok(memes)
+    }
+
+    /// The lines of the mood, the neutral ones if the packs dont have any for it
+    fn lines(&self, mood: Mood) -> &[Meme] {
+        let lines = match mood {
+            Mood::Neutral => &self.neutral,
+            Mood::Moon => &self.moon,
+            Mood::Pain => &self.pain,
+        };
+        match lines.is_empty() {
+            true => &self.neutral,
+            false => lines,
+        }
+    }
+}
+
+/// The first `{...}` in the line that isnt one of the [`PLACEHOLDERS`]
+fn unknown_placeholder(line: &str) -> Option<&str> {
+    let mut rest = line;
+    while let Some(start) = rest.find('{') {
+        let end = start + rest[start..].find('}')?;
+        let p = &rest[start..=end];
+        if !PLACEHOLDERS.contains(&p) {
+            return Some(p);
+        }
+        rest = &rest[end + 1..];
+    }
+    None
+}
+
+/// What the placeholders get filled with
+#[derive(Debug, Clone, Default)]
+struct Market {
+    product: String,
+    /// Percent over the chart timeframe
+    change: Option<f64>,
+    price: Option<f64>,
+}
+
+impl Market {
+    fn of(product: &str, window_secs: u64) -> Self {
+        let prices = window_prices(product, window_secs as f64 * 1000.0);
+        let change = prices
+            .filter(|(first, _)| *first != 0.0)
+            .map(|(first, last)| (last - first) / first * 100.0);
+        Self {
+            product: product.to_string(),
+            change,
+            price: prices.map(|(_, last)| last),
+        }
+    }
+
+    fn fill(&self, line: &str) -> String {
+        let coin = self.product.split('-').next().unwrap_or_default();
+        let change = self
+            .change
+            .map_or("?%".to_string(), |c| format!("{c:+.2}%"));
+        let price = self.price.map_or("?".to_string(), |p| format!("{p:.2}"));
+        line.replace("{coin}", coin)
+            .replace("{product}", &self.product)
+            .replace("{change}", &change)
+            .replace("{price}", &price)
     }
 }
 
-pub const MEMES: [(&'static str, &'static str); 2] = [
-    ("me when the stonks go brr", "bottom text."),
-    ("helo", "henlo"),
-];
+/// The meme that is shown right now
+#[derive(Debug, Clone)]
+pub struct Banner {
+    rng: XorShift32,
+    mood: Mood,
+    /// Index into the lines of the mood
+    line: usize,
+    /// Unix ms when the next line gets picked
+    next_at: i64,
+    market: Market,
+}
+
+impl Banner {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: XorShift32::new(seed.max(1)),
+            mood: Mood::default(),
+            line: 0,
+            next_at: 0,
+            market: Market::default(),
+        }
+    }
+
+    /// Looks at the focused product and picks a new line when the time is up or the mood changed,
+    /// true if it did
+    pub fn update(
+        &mut self,
+        memes: &Memes,
+        product: Option<&str>,
+        window_secs: u64,
+        threshold: f64,
+        rotate_secs: u64,
+    ) -> bool {
+        self.market = product
+            .map(|p| Market::of(p, window_secs))
+            .unwrap_or_default();
+        let margin = threshold * MOOD_MARGIN;
+        let mood = match (self.mood, self.market.change) {
+            (_, Some(c)) if c >= threshold => Mood::Moon,
+            (_, Some(c)) if c <= -threshold => Mood::Pain,
+            (Mood::Moon, Some(c)) if c > threshold - margin => Mood::Moon,
+            (Mood::Pain, Some(c)) if c < -threshold + margin => Mood::Pain,
+            _ => Mood::Neutral,
+        };
+
+        let now = chrono::Local::now().timestamp_millis();
+        let lines = memes.lines(mood).len();
+        // a reloaded config can have less lines
+        if mood == self.mood && now < self.next_at && self.line < lines {
+            return false;
+        }
+        self.mood = mood;
+        self.line = self.rng.gen_range(lines.max(1));
+        self.next_at = now + rotate_secs as i64 * 1000;
+        true
+    }
+
+    /// Header and footer with the placeholders filled in
+    pub fn text(&self, memes: &Memes) -> (String, String) {
+        match memes.lines(self.mood).get(self.line) {
+            Some((top, bottom)) => (self.market.fill(top), self.market.fill(bottom)),
+            None => Default::default(),
+        }
+    }
+}
@@ -1,13 +1,109 @@
-use anyhow::Result;
+use std::path::PathBuf;
+
 use clap::Parser;
 
-fn stov(v: &str) -> Result<Vec<String>> {
-    Ok(v.split(',').map(|f| f.trim().to_string()).collect())
-}
+use crate::{
+    app::WindowType,
+    arbitrage::DEFAULT_THRESHOLD,
+    ledger::CostBasisMethod,
+    terminal::{BorderStyle, ColorSupport},
+    utils::EXCHANGE_API_URL,
+};
 
-#[derive(Debug, Parser)]
+#[derive(Debug, Clone, Parser)]
 pub struct CliOpts {
-    /// The coins that should be watched in a list like BTC-USDC,SOL-USDC
-    #[arg(short = 'w', long = "watching", default_value = "SOL-USDC", value_delimiter = ',')]
-    pub watching: Vec<String>,
+    /// The coins that should be watched in a list like BTC-USDC,SOL-USDC, defaults to the
+    /// `watching` list of the config or SOL-USDC
+    #[arg(short = 'w', long = "watching", value_delimiter = ',')]
+    pub watching: Option<Vec<String>>,
+
+    /// Watch a named watchlist from the config file instead of --watching
+    #[arg(long = "watchlist", conflicts_with = "watching")]
+    pub watchlist: Option<String>,
+
+    /// Path to the config file, defaults to config.toml in the config directory
+    #[arg(long = "config")]
+    pub config: Option<PathBuf>,
+
+    /// The window in seconds that the buy/ sell pressure of a chart is calculated over
+    #[arg(short = 'p', long = "pressure-window", value_parser = clap::value_parser!(u64).range(1..))]
+    pub pressure_window: Option<u64>,
+
+    /// How many ticker messages are kept per product
+    #[arg(
+        long = "buffer-size",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub buffer_size: Option<usize>,
+
+    /// How often the indicators, alerts etc. get updated, in ms
+    #[arg(long = "tick-rate", value_parser = clap::value_parser!(u64).range(1..))]
+    pub tick_rate: Option<u64>,
+
+    /// How often the screen gets redrawn per second at most, frames without changes are skipped
+    #[arg(long = "frame-rate", value_parser = clap::value_parser!(u64).range(1..))]
+    pub frame_rate: Option<u64>,
+
+    /// How far the charts look back in seconds
+    #[arg(short = 't', long = "timeframe", value_parser = clap::value_parser!(u64).range(1..))]
+    pub timeframe: Option<u64>,
+
+    /// How the charts are arranged
+    #[arg(long = "layout", value_enum)]
+    pub layout: Option<WindowType>,
+
+    /// The color theme, dark, light, high-contrast or the name of an own theme in the themes
+    /// directory of the config
+    #[arg(long = "theme")]
+    pub theme: Option<String>,
+
+    /// How many colors the terminal can show, detected from COLORTERM, TERM and NO_COLOR by
+    /// default
+    #[arg(long = "colors", value_enum)]
+    pub colors: Option<ColorSupport>,
+
+    /// The characters the borders are drawn with, ascii for terminals without box drawing
+    /// characters
+    #[arg(long = "borders", value_enum)]
+    pub borders: Option<BorderStyle>,
+
+    /// Keep the borders of the charts still, `g` toggles it while running
+    #[arg(long = "no-animation")]
+    pub no_animation: bool,
+
+    /// Path to the portfolio file, defaults to portfolio.toml in the config directory
+    #[arg(long = "portfolio")]
+    pub portfolio: Option<PathBuf>,
+
+    /// Import a transaction history CSV into the ledger and exit. Expects the Coinbase export
+    /// format unless --columns is given
+    #[arg(long = "import")]
+    pub import: Option<PathBuf>,
+
+    /// Column mapping for CSVs that arent Coinbase exports, like
+    /// timestamp=Date,kind=Type,asset=Coin,quantity=Amount,price=Price,fee=Fee,currency=Currency
+    #[arg(long = "columns", requires = "import")]
+    pub columns: Option<String>,
+
+    /// How the cost basis of the ledger gets calculated
+    #[arg(long = "cost-basis", value_enum, default_value_t = CostBasisMethod::Fifo)]
+    pub cost_basis: CostBasisMethod,
+
+    /// Show the charts in this currency, like EUR. The rates come from the feed, so there has to be
+    /// a path of pairs from the quote currency to it, see --rate-products
+    #[arg(short = 'd', long = "display-currency")]
+    pub display_currency: Option<String>,
+
+    /// Extra products that only get subscribed for conversion rates, like USDT-EUR,BTC-EUR. They
+    /// have to exist on the exchange, otherwise the feed rejects the whole subscription
+    #[arg(long = "rate-products", value_delimiter = ',')]
+    pub rate_products: Vec<String>,
+
+    /// Base url of the exchange api that the product list gets fetched from
+    #[arg(long = "api-url", default_value = EXCHANGE_API_URL)]
+    pub api_url: String,
+
+    /// Spreads between quotes of the same asset at or above this many percent get highlighted
+    #[arg(long = "arb-threshold", default_value_t = DEFAULT_THRESHOLD)]
+    pub arb_threshold: f64,
 }
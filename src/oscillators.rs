@@ -0,0 +1,377 @@
+//! Momentum oscillators
+//!
+//! Unlike the overlays in [`crate::indicators`] these dont share the price scale, so they get
+//! their own small pane below the price chart.
+
+use std::collections::VecDeque;
+
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Stylize},
+    widgets::{Axis, Chart, Dataset, GraphType, Widget},
+};
+
+use crate::theme::Theme;
+
+/// How many calculated points an oscillator keeps, matches the size of the message buffer
+const MAX_POINTS: usize = 10000;
+
+const REFERENCE_COLOR: Color = Color::Rgb(90, 90, 90);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OscillatorKind {
+    /// Relative strength index over n ticks
+    Rsi(usize),
+    /// Moving average convergence divergence with the fast, slow and signal periods
+    Macd(usize, usize, usize),
+    /// Stochastic oscillator with the %K and %D periods
+    Stochastic(usize, usize),
+}
+
+impl OscillatorKind {
+    pub const RSI: OscillatorKind = OscillatorKind::Rsi(14);
+    pub const MACD: OscillatorKind = OscillatorKind::Macd(12, 26, 9);
+    pub const STOCHASTIC: OscillatorKind = OscillatorKind::Stochastic(14, 3);
+
+    /// The oscillator that comes after this one, `None` means the pane is hidden
+    pub fn cycle(current: Option<OscillatorKind>) -> Option<OscillatorKind> {
+        match current {
+            None => Some(Self::RSI),
+            Some(OscillatorKind::Rsi(_)) => Some(Self::MACD),
+            Some(OscillatorKind::Macd(..)) => Some(Self::STOCHASTIC),
+            Some(OscillatorKind::Stochastic(..)) => None,
+        }
+    }
+
+    fn label(&self) -> String {
+        match self {
+            OscillatorKind::Rsi(p) => format!("RSI{p}"),
+            OscillatorKind::Macd(f, s, sig) => format!("MACD{f},{s},{sig}"),
+            OscillatorKind::Stochastic(k, d) => format!("STOCH{k},{d}"),
+        }
+    }
+
+    /// The values where a reference line gets drawn
+    fn reference_lines(&self) -> &'static [f64] {
+        match self {
+            OscillatorKind::Rsi(_) => &[30.0, 70.0],
+            OscillatorKind::Macd(..) => &[0.0],
+            OscillatorKind::Stochastic(..) => &[20.0, 80.0],
+        }
+    }
+}
+
+/// Exponential moving average that is used as a building block for the oscillators
+#[derive(Debug, Clone)]
+struct Ema {
+    alpha: f64,
+    value: Option<f64>,
+}
+
+impl Ema {
+    fn new(period: usize) -> Self {
+        Self {
+            alpha: 2.0 / (period as f64 + 1.0),
+            value: None,
+        }
+    }
+
+    fn push(&mut self, v: f64) -> f64 {
+        let next = match self.value {
+            Some(prev) => self.alpha * v + (1.0 - self.alpha) * prev,
+            None => v,
+        };
+        self.value = Some(next);
+        next
+    }
+}
+
+/// The running state of an oscillator
+#[derive(Debug, Clone)]
+enum State {
+    Rsi {
+        prev: Option<f64>,
+        seen: usize,
+        avg_gain: f64,
+        avg_loss: f64,
+    },
+    Macd {
+        fast: Ema,
+        slow: Ema,
+        signal: Ema,
+        seen: usize,
+    },
+    Stochastic {
+        window: VecDeque<f64>,
+        k_window: VecDeque<f64>,
+    },
+}
+
+#[derive(Debug, Clone)]
+pub struct Oscillator {
+    pub kind: OscillatorKind,
+    state: State,
+    /// RSI has one line, stochastic has %K and %D, MACD has the macd, signal and histogram
+    lines: Vec<VecDeque<(f64, f64)>>,
+}
+
+impl Oscillator {
+    pub fn new(kind: OscillatorKind) -> Self {
+        let (state, line_count) = match kind {
+            OscillatorKind::Rsi(_) => (
+                State::Rsi {
+                    prev: None,
+                    seen: 0,
+                    avg_gain: 0.0,
+                    avg_loss: 0.0,
+                },
+                1,
+            ),
+            OscillatorKind::Macd(fast, slow, signal) => (
+                State::Macd {
+                    fast: Ema::new(fast),
+                    slow: Ema::new(slow),
+                    signal: Ema::new(signal),
+                    seen: 0,
+                },
+                3,
+            ),
+            OscillatorKind::Stochastic(..) => (
+                State::Stochastic {
+                    window: VecDeque::new(),
+                    k_window: VecDeque::new(),
+                },
+                2,
+            ),
+        };
+
+        Self {
+            kind,
+            state,
+            lines: vec![VecDeque::new(); line_count],
+        }
+    }
+
+    /// Feeds the next price into the oscillator
+    pub fn push(&mut self, time: f64, price: f64) {
+        let values = match (&mut self.state, self.kind) {
+            (
+                State::Rsi {
+                    prev,
+                    seen,
+                    avg_gain,
+                    avg_loss,
+                },
+                OscillatorKind::Rsi(period),
+            ) => {
+                let Some(p) = prev.replace(price) else {
+                    return;
+                };
+                let change = price - p;
+                let (gain, loss) = (change.max(0.0), (-change).max(0.0));
+                let n = period as f64;
+                *seen += 1;
+
+                // the first averages are simple ones, after that wilders smoothing takes over
+                if *seen <= period {
+                    *avg_gain += gain / n;
+                    *avg_loss += loss / n;
+                } else {
+                    *avg_gain = (*avg_gain * (n - 1.0) + gain) / n;
+                    *avg_loss = (*avg_loss * (n - 1.0) + loss) / n;
+                }
+
+                if *seen < period {
+                    return;
+                }
+
+                let rsi = if *avg_loss == 0.0 {
+                    100.0
+                } else {
+                    100.0 - 100.0 / (1.0 + *avg_gain / *avg_loss)
+                };
+                vec![rsi]
+            }
+            (
+                State::Macd {
+                    fast,
+                    slow,
+                    signal,
+                    seen,
+                },
+                OscillatorKind::Macd(_, slow_period, _),
+            ) => {
+                let macd = fast.push(price) - slow.push(price);
+                let sig = signal.push(macd);
+                *seen += 1;
+
+                if *seen < slow_period {
+                    return;
+                }
+                vec![macd, sig, macd - sig]
+            }
+            (State::Stochastic { window, k_window }, OscillatorKind::Stochastic(k, d)) => {
+                window.push_back(price);
+                if window.len() > k {
+                    window.pop_front();
+                }
+                if window.len() < k {
+                    return;
+                }
+
+                let lo = window.iter().copied().fold(f64::INFINITY, f64::min);
+                let hi = window.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                let percent_k = if hi == lo {
+                    50.0
+                } else {
+                    (price - lo) / (hi - lo) * 100.0
+                };
+
+                k_window.push_back(percent_k);
+                if k_window.len() > d {
+                    k_window.pop_front();
+                }
+                let percent_d = k_window.iter().sum::<f64>() / k_window.len() as f64;
+
+                vec![percent_k, percent_d]
+            }
+            _ => return,
+        };
+
+        for (line, v) in self.lines.iter_mut().zip(values) {
+            line.push_back((time, v));
+            if line.len() > MAX_POINTS {
+                line.pop_front();
+            }
+        }
+    }
+
+    /// Builds the chart for the oscillator pane. `bounds` is the time axis of the price chart and
+    /// `label_width` the width of its price labels, so both charts line up.
+    pub fn chart<'a>(
+        &self,
+        bounds: [f64; 2],
+        label_width: usize,
+        data: &'a [Vec<(f64, f64)>],
+        theme: &Theme,
+    ) -> Chart<'a> {
+        let colors = [
+            Color::Rgb(0, 200, 255),
+            Color::Rgb(255, 165, 0),
+            Color::Rgb(160, 160, 160),
+        ];
+
+        let visible = data
+            .iter()
+            .flat_map(|l| l.iter().filter(|(t, _)| *t >= bounds[0]).map(|(_, v)| *v));
+
+        let [lo, hi] = match self.kind {
+            OscillatorKind::Rsi(_) | OscillatorKind::Stochastic(..) => [0.0, 100.0],
+            OscillatorKind::Macd(..) => {
+                let max = visible
+                    .fold(0.0f64, |m, v| m.max(v.abs()))
+                    .max(f64::EPSILON);
+                [-max * 1.1, max * 1.1]
+            }
+        };
+
+        let line_count = self.lines.len();
+        let datasets = data
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                // everything after the calculated lines are the reference lines
+                if i >= line_count {
+                    return Dataset::default()
+                        .style(REFERENCE_COLOR)
+                        .marker(theme.marker)
+                        .graph_type(GraphType::Line)
+                        .data(line);
+                }
+
+                let is_hist = matches!(self.kind, OscillatorKind::Macd(..)) && i == 2;
+                Dataset::default()
+                    .style(colors[i % colors.len()])
+                    .marker(theme.marker)
+                    .graph_type(if is_hist {
+                        GraphType::Bar
+                    } else {
+                        GraphType::Line
+                    })
+                    .data(line)
+            })
+            .collect::<Vec<Dataset>>();
+
+        let labels = match self.kind {
+            OscillatorKind::Macd(..) => vec![
+                format!("{lo:>label_width$.2}"),
+                format!("{:>label_width$}", "0"),
+                format!("{hi:>label_width$.2}"),
+            ],
+            _ => vec![
+                format!("{:>label_width$}", "0"),
+                format!("{:>label_width$}", "50"),
+                format!("{:>label_width$}", "100"),
+            ],
+        };
+
+        Chart::new(datasets)
+            .x_axis(
+                Axis::default()
+                    .title(self.kind.label().fg(theme.muted))
+                    .bounds(bounds)
+                    .style(theme.axis),
+            )
+            .y_axis(
+                Axis::default()
+                    .bounds([lo, hi])
+                    .labels(labels.into_iter().map(|l| l.fg(theme.label)))
+                    .style(theme.axis),
+            )
+            .legend_position(None)
+    }
+
+    /// The points of every line, plus the reference lines which span over `bounds`
+    pub fn lines(&self, bounds: [f64; 2]) -> Vec<Vec<(f64, f64)>> {
+        let mut lines = self
+            .lines
+            .iter()
+            .map(|l| l.iter().copied().collect())
+            .collect::<Vec<Vec<(f64, f64)>>>();
+
+        lines.extend(
+            self.kind
+                .reference_lines()
+                .iter()
+                .map(|v| vec![(bounds[0], *v), (bounds[1], *v)]),
+        );
+        lines
+    }
+}
+
+/// Renders a chart with an optional oscillator pane below it that takes up a quarter of the height
+pub struct WithOscillator<'a, W> {
+    chart: W,
+    pane: Option<Chart<'a>>,
+}
+
+impl<'a, W> WithOscillator<'a, W> {
+    pub fn new(chart: W, pane: Option<Chart<'a>>) -> Self {
+        Self { chart, pane }
+    }
+}
+
+impl<W: Widget> Widget for WithOscillator<'_, W> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let Some(pane) = self.pane else {
+            self.chart.render(area, buf);
+            return;
+        };
+
+        let [top, bottom] =
+            Layout::vertical([Constraint::Fill(3), Constraint::Fill(1)]).areas(area);
+        self.chart.render(top, buf);
+        pane.render(bottom, buf);
+    }
+}
@@ -0,0 +1,287 @@
+//! Market overview
+//!
+//! A table of every watched product with the most important numbers of the ticker feed, for when
+//! there are too many coins to fit into the chart grid.
+
+use chrono::{DateTime, Utc};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Cell, Row, Table, TableState},
+};
+use ringbuffer::RingBuffer;
+
+use crate::{
+    sockets::{price_history, ws_messages},
+    theme::Theme,
+};
+
+const SPARK_CHARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const SPARK_WIDTH: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Column {
+    #[default]
+    Product,
+    Price,
+    Change,
+    High,
+    Low,
+    Volume24h,
+    Volume30d,
+    Spread,
+    Age,
+}
+
+impl Column {
+    pub const ALL: [Column; 9] = [
+        Column::Product,
+        Column::Price,
+        Column::Change,
+        Column::High,
+        Column::Low,
+        Column::Volume24h,
+        Column::Volume30d,
+        Column::Spread,
+        Column::Age,
+    ];
+
+    fn title(&self) -> &'static str {
+        match self {
+            Column::Product => "Product",
+            Column::Price => "Price",
+            Column::Change => "24h %",
+            Column::High => "24h High",
+            Column::Low => "24h Low",
+            Column::Volume24h => "24h Vol",
+            Column::Volume30d => "30d Vol",
+            Column::Spread => "Spread",
+            Column::Age => "Age",
+        }
+    }
+
+    /// The column to the right of this one, wraps around at the end
+    pub fn next(self) -> Self {
+        let idx = Self::ALL.iter().position(|c| *c == self).unwrap_or(0);
+        Self::ALL[(idx + 1) % Self::ALL.len()]
+    }
+}
+
+/// The latest numbers of a single product
+#[derive(Debug, Clone, Default)]
+pub struct MarketRow {
+    pub product: String,
+    pub price: f64,
+    /// Percent change since `open_24h`
+    pub change: f64,
+    pub high: f64,
+    pub low: f64,
+    pub volume_24h: f64,
+    pub volume_30d: f64,
+    /// Difference between the best ask and the best bid
+    pub spread: f64,
+    /// Seconds since the last message of this product
+    pub age: f64,
+    pub spark: String,
+}
+
+impl MarketRow {
+    /// Builds the row from the newest message of the product, `None` if nothing arrived yet
+    pub fn from_feed(product: &str) -> Option<Self> {
+        let last = ws_messages.lock().get(product)?.back()?.clone();
+        let num = |v: &str| v.parse::<f64>().unwrap_or(0.0);
+
+        let price = num(&last.price);
+        let open = num(&last.open_24h);
+        let change = if open == 0.0 {
+            0.0
+        } else {
+            (price / open - 1.0) * 100.0
+        };
+        let age = last
+            .time
+            .parse::<DateTime<Utc>>()
+            .map(|t| (Utc::now() - t).num_milliseconds() as f64 / 1000.0)
+            .unwrap_or(0.0);
+
+        Some(Self {
+            product: product.to_string(),
+            price,
+            change,
+            high: num(&last.high_24h),
+            low: num(&last.low_24h),
+            volume_24h: num(&last.volume_24h),
+            volume_30d: num(&last.volume_30d),
+            spread: num(&last.best_ask) - num(&last.best_bid),
+            age: age.max(0.0),
+            spark: sparkline(&price_history(product)),
+        })
+    }
+
+    fn cmp_by(&self, other: &Self, column: Column) -> std::cmp::Ordering {
+        let key = |r: &Self| match column {
+            Column::Product => 0.0,
+            Column::Price => r.price,
+            Column::Change => r.change,
+            Column::High => r.high,
+            Column::Low => r.low,
+            Column::Volume24h => r.volume_24h,
+            Column::Volume30d => r.volume_30d,
+            Column::Spread => r.spread,
+            Column::Age => r.age,
+        };
+
+        match column {
+            Column::Product => self.product.cmp(&other.product),
+            _ => key(self).total_cmp(&key(other)),
+        }
+    }
+}
+
+/// Turns the last prices into a small unicode block chart
+pub fn sparkline(points: &[(f64, f64)]) -> String {
+    if points.is_empty() {
+        return String::new();
+    }
+
+    // only take every nth point so the whole buffer fits into the column
+    let step = points.len().div_ceil(SPARK_WIDTH).max(1);
+    let prices = points
+        .iter()
+        .step_by(step)
+        .map(|(_, p)| *p)
+        .collect::<Vec<f64>>();
+
+    let lo = prices.iter().copied().fold(f64::INFINITY, f64::min);
+    let hi = prices.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    let range = hi - lo;
+
+    prices
+        .iter()
+        .map(|p| {
+            if range == 0.0 {
+                return SPARK_CHARS[SPARK_CHARS.len() / 2];
+            }
+            let idx = ((p - lo) / range * (SPARK_CHARS.len() - 1) as f64).round() as usize;
+            SPARK_CHARS[idx.min(SPARK_CHARS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Shortens big numbers, e.g. 1234567 becomes 1.23M
+pub fn human(v: f64) -> String {
+    match v.abs() {
+        x if x >= 1e9 => format!("{:.2}B", v / 1e9),
+        x if x >= 1e6 => format!("{:.2}M", v / 1e6),
+        x if x >= 1e3 => format!("{:.2}K", v / 1e3),
+        _ => format!("{v:.2}"),
+    }
+}
+
+/// The selection and sorting of the overview table
+#[derive(Debug, Clone, Default)]
+pub struct OverviewState {
+    pub selected: usize,
+    pub sort_by: Column,
+    pub descending: bool,
+}
+
+impl OverviewState {
+    /// The rows of every product that already got a message, in the current sort order
+    pub fn rows(&self, products: &[String]) -> Vec<MarketRow> {
+        let mut rows = products
+            .iter()
+            .filter_map(|p| MarketRow::from_feed(p))
+            .collect::<Vec<MarketRow>>();
+
+        rows.sort_by(|a, b| {
+            let ord = a.cmp_by(b, self.sort_by);
+            if self.descending { ord.reverse() } else { ord }
+        });
+        rows
+    }
+
+    /// Moves the selection by `by` rows, wrapping around at both ends
+    pub fn select(&mut self, by: isize, len: usize) {
+        if len == 0 {
+            self.selected = 0;
+            return;
+        }
+        self.selected = (self.selected as isize + by).rem_euclid(len as isize) as usize;
+    }
+
+    /// Sorts by the next column, or flips the order if `reverse` is set
+    pub fn sort(&mut self, reverse: bool) {
+        if reverse {
+            self.descending = !self.descending;
+        } else {
+            self.sort_by = self.sort_by.next();
+        }
+    }
+}
+
+pub fn render_overview(
+    frame: &mut Frame,
+    area: Rect,
+    products: &[String],
+    state: &OverviewState,
+    theme: &Theme,
+) {
+    let rows = state.rows(products);
+
+    let header = Row::new(
+        Column::ALL
+            .iter()
+            .map(|c| {
+                let arrow = match (*c == state.sort_by, state.descending) {
+                    (true, true) => " ▼",
+                    (true, false) => " ▲",
+                    _ => "",
+                };
+                Cell::from(format!("{}{arrow}", c.title()))
+            })
+            // the sparkline isnt sortable so it doesnt have a column entry
+            .chain([Cell::from("Trend")]),
+    )
+    .style(Style::new().bold().fg(theme.label));
+
+    let body = rows.iter().map(|r| {
+        let change_color = if r.change >= 0.0 {
+            theme.up
+        } else {
+            theme.down
+        };
+
+        Row::new(vec![
+            Cell::from(r.product.clone()),
+            Cell::from(Line::from(format!("{:.2}", r.price)).right_aligned()),
+            Cell::from(Line::from(format!("{:+.2}%", r.change)).right_aligned()).fg(change_color),
+            Cell::from(Line::from(format!("{:.2}", r.high)).right_aligned()),
+            Cell::from(Line::from(format!("{:.2}", r.low)).right_aligned()),
+            Cell::from(Line::from(human(r.volume_24h)).right_aligned()),
+            Cell::from(Line::from(human(r.volume_30d)).right_aligned()),
+            Cell::from(Line::from(format!("{:.4}", r.spread)).right_aligned()),
+            Cell::from(Line::from(format!("{:.0}s", r.age)).right_aligned()),
+            Cell::from(r.spark.clone()).fg(change_color),
+        ])
+        .style(Style::new().fg(theme.label))
+    });
+
+    let mut widths = vec![Constraint::Length(12); Column::ALL.len()];
+    widths.push(Constraint::Length(SPARK_WIDTH as u16));
+
+    let table = Table::new(body, widths)
+        .header(header)
+        .row_highlight_style(Style::new().reversed())
+        .highlight_symbol("> ");
+
+    let mut table_state = TableState::default().with_selected(Some(state.selected));
+
+    let widget = theme
+        .panel(table)
+        .title(format!("Overview - {} products", rows.len()));
+
+    frame.render_stateful_widget(widget, area, &mut table_state);
+}
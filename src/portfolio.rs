@@ -0,0 +1,335 @@
+//! Portfolio
+//!
+//! Holdings are declared in `portfolio.toml` in the config directory and valued live with the
+//! prices of the ticker feed.
+//!
+//! ```toml
+//! # the currency everything gets valued in
+//! quote = "USD"
+//!
+//! [[holding]]
+//! asset = "BTC"
+//! quantity = 0.25
+//! account = "coinbase"
+//!
+//! [[holding]]
+//! asset = "SOL"
+//! quantity = 40
+//! ```
+
+use std::{collections::VecDeque, path::Path};
+
+use anyhow::Context;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Axis, Cell, Chart, Dataset, Row, Table},
+};
+use serde::Deserialize;
+
+use crate::{
+    sockets::last_message,
+    theme::Theme,
+    utils::{config_dir, convert_timestamp_to_locale},
+};
+
+/// How many total values of the session are kept for the chart
+const MAX_HISTORY: usize = 10000;
+
+fn default_quote() -> String {
+    "USD".to_string()
+}
+
+fn default_account() -> String {
+    "default".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Holding {
+    /// The base asset, e.g. `BTC`
+    pub asset: String,
+    pub quantity: f64,
+    /// Where the asset is held, only used for grouping
+    #[serde(default = "default_account")]
+    pub account: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Portfolio {
+    /// The currency that the holdings get valued in
+    #[serde(default = "default_quote")]
+    pub quote: String,
+    #[serde(default, rename = "holding")]
+    pub holdings: Vec<Holding>,
+
+    /// Total value of the portfolio over the session, as `(timestamp_ms, value)`
+    #[serde(skip)]
+    history: VecDeque<(f64, f64)>,
+}
+
+impl Default for Portfolio {
+    fn default() -> Self {
+        Self {
+            quote: default_quote(),
+            holdings: vec![],
+            history: VecDeque::new(),
+        }
+    }
+}
+
+/// A holding together with its current value
+#[derive(Debug, Clone)]
+pub struct Valuation {
+    pub asset: String,
+    pub account: String,
+    pub quantity: f64,
+    /// `None` if the feed didnt send a price for the product yet
+    pub price: Option<f64>,
+    pub value: f64,
+    /// Change of the value since `open_24h`
+    pub day_change: f64,
+}
+
+impl Portfolio {
+    /// The default location of the portfolio file
+    pub fn default_path() -> std::path::PathBuf {
+        config_dir().join("portfolio.toml")
+    }
+
+    /// Loads the portfolio, a missing file just means there are no holdings
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+
+        let raw = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&raw).with_context(|| format!("Invalid portfolio in {}", path.display()))
+    }
+
+    /// The products that need to be subscribed to value the holdings
+    pub fn products(&self) -> Vec<String> {
+        let mut products = self
+            .holdings
+            .iter()
+            .filter(|h| h.asset != self.quote)
+            .map(|h| format!("{}-{}", h.asset, self.quote))
+            .collect::<Vec<String>>();
+        products.sort();
+        products.dedup();
+        products
+    }
+
+    /// Values every holding with the latest price of the feed
+    pub fn valuations(&self) -> Vec<Valuation> {
+        self.holdings
+            .iter()
+            .map(|h| {
+                // the quote currency itself is always worth 1
+                let (price, open) = if h.asset == self.quote {
+                    (Some(1.0), 1.0)
+                } else {
+                    match last_message(&format!("{}-{}", h.asset, self.quote)) {
+                        Some(m) => (
+                            m.price.parse::<f64>().ok(),
+                            m.open_24h.parse::<f64>().unwrap_or(0.0),
+                        ),
+                        None => (None, 0.0),
+                    }
+                };
+
+                let value = price.unwrap_or(0.0) * h.quantity;
+                let day_change = match price {
+                    Some(p) if open > 0.0 => (p - open) * h.quantity,
+                    _ => 0.0,
+                };
+
+                Valuation {
+                    asset: h.asset.clone(),
+                    account: h.account.clone(),
+                    quantity: h.quantity,
+                    price,
+                    value,
+                    day_change,
+                }
+            })
+            .collect()
+    }
+
+    /// Records the current total value for the session chart
+    pub fn record(&mut self) {
+        if self.holdings.is_empty() {
+            return;
+        }
+
+        let valuations = self.valuations();
+        // dont record anything until every price is known, otherwise the chart starts with a jump
+        if valuations.iter().any(|v| v.price.is_none()) {
+            return;
+        }
+
+        let total = valuations.iter().map(|v| v.value).sum::<f64>();
+        self.history
+            .push_back((chrono::Local::now().timestamp_millis() as f64, total));
+        if self.history.len() > MAX_HISTORY {
+            self.history.pop_front();
+        }
+    }
+}
+
+pub fn render_portfolio(frame: &mut Frame, area: Rect, portfolio: &Portfolio, theme: &Theme) {
+    let valuations = portfolio.valuations();
+    let total = valuations.iter().map(|v| v.value).sum::<f64>();
+    let total_change = valuations.iter().map(|v| v.day_change).sum::<f64>();
+    let quote = &portfolio.quote;
+
+    let [table_area, chart_area] =
+        Layout::vertical([Constraint::Fill(1), Constraint::Fill(1)]).areas(area);
+
+    let change_color = |c: f64| {
+        if c >= 0.0 { theme.up } else { theme.down }
+    };
+
+    let header = Row::new(
+        [
+            "Asset", "Account", "Quantity", "Price", "Value", "Alloc", "Day",
+        ]
+        .iter()
+        .enumerate()
+        .map(|(i, h)| match i {
+            0 | 1 => Cell::from(*h),
+            _ => Cell::from(Line::from(*h).right_aligned()),
+        }),
+    )
+    .style(Style::new().bold().fg(theme.label));
+
+    let mut rows = valuations
+        .iter()
+        .map(|v| {
+            let alloc = if total > 0.0 {
+                v.value / total * 100.0
+            } else {
+                0.0
+            };
+            let price = v
+                .price
+                .map(|p| format!("{p:.2}"))
+                .unwrap_or("-".to_string());
+
+            Row::new(vec![
+                Cell::from(v.asset.clone()),
+                Cell::from(v.account.clone()),
+                Cell::from(Line::from(format!("{}", v.quantity)).right_aligned()),
+                Cell::from(Line::from(price).right_aligned()),
+                Cell::from(Line::from(format!("{:.2} {quote}", v.value)).right_aligned()),
+                Cell::from(Line::from(format!("{alloc:.1}%")).right_aligned()),
+                Cell::from(Line::from(format!("{:+.2}", v.day_change)).right_aligned())
+                    .fg(change_color(v.day_change)),
+            ])
+            .style(Style::new().fg(theme.label))
+        })
+        .collect::<Vec<Row>>();
+
+    let prev_total = total - total_change;
+    let total_percent = if prev_total > 0.0 {
+        total_change / prev_total * 100.0
+    } else {
+        0.0
+    };
+    rows.push(
+        Row::new(vec![
+            Cell::from("Total"),
+            Cell::from(""),
+            Cell::from(""),
+            Cell::from(""),
+            Cell::from(Line::from(format!("{total:.2} {quote}")).right_aligned()),
+            Cell::from(Line::from("100.0%").right_aligned()),
+            Cell::from(
+                Line::from(format!("{total_change:+.2} ({total_percent:+.2}%)")).right_aligned(),
+            )
+            .fg(change_color(total_change)),
+        ])
+        .style(Style::new().bold().fg(theme.label))
+        .top_margin(1),
+    );
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(8),
+            Constraint::Length(12),
+            Constraint::Length(14),
+            Constraint::Length(12),
+            Constraint::Length(18),
+            Constraint::Length(8),
+            Constraint::Fill(1),
+        ],
+    )
+    .header(header);
+
+    frame.render_widget(
+        theme
+            .panel(table)
+            .title(format!("Portfolio - {} holdings", valuations.len())),
+        table_area,
+    );
+
+    let data = portfolio
+        .history
+        .iter()
+        .copied()
+        .collect::<Vec<(f64, f64)>>();
+    let (start, end) = match (data.first(), data.last()) {
+        (Some(f), Some(l)) => (f.0, l.0.max(f.0 + 1.0)),
+        _ => {
+            let now = chrono::Local::now().timestamp_millis() as f64;
+            (now, now + 1.0)
+        }
+    };
+    let lo = data.iter().map(|d| d.1).fold(f64::INFINITY, f64::min);
+    let hi = data.iter().map(|d| d.1).fold(f64::NEG_INFINITY, f64::max);
+    let (lo, hi) = if lo.is_finite() && hi.is_finite() {
+        let pad = ((hi - lo) * 0.1).max(hi.abs() * 0.0001).max(0.01);
+        (lo - pad, hi + pad)
+    } else {
+        (0.0, 1.0)
+    };
+    let session_change = match (data.first(), data.last()) {
+        (Some(f), Some(l)) => l.1 - f.1,
+        _ => 0.0,
+    };
+
+    let chart = Chart::new(vec![
+        Dataset::default()
+            .style(change_color(session_change))
+            .marker(theme.marker)
+            .data(&data),
+    ])
+    .x_axis(
+        Axis::default()
+            .bounds([start, end])
+            .labels([
+                convert_timestamp_to_locale(start).fg(theme.label),
+                convert_timestamp_to_locale(end).fg(theme.label),
+            ])
+            .style(theme.axis),
+    )
+    .y_axis(
+        Axis::default()
+            .bounds([lo, hi])
+            .labels([
+                format!("{lo:.2}").fg(theme.label),
+                format!("{hi:.2}").fg(theme.label),
+            ])
+            .style(theme.axis),
+    );
+
+    frame.render_widget(
+        theme
+            .panel(chart)
+            .title(format!("Session {session_change:+.2} {quote}")),
+        chart_area,
+    );
+}
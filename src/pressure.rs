@@ -0,0 +1,126 @@
+//! Buy/ sell pressure
+//!
+//! Weighs every trade by its size instead of just counting messages, and only looks at the trades
+//! inside a rolling time window.
+
+use chrono::{DateTime, Utc};
+use ratatui::{
+    buffer::Buffer,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style},
+    symbols,
+    widgets::{LineGauge, Widget},
+};
+
+use crate::sockets::WsMessage;
+
+/// The default size of the rolling window in seconds
+pub const DEFAULT_PRESSURE_WINDOW: u64 = 60;
+
+/// Size weighted buy and sell volume inside a time window
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Pressure {
+    pub buy_volume: f64,
+    pub sell_volume: f64,
+}
+
+impl Pressure {
+    /// Sums up the traded size of every message that is newer than `since` (unix ms)
+    pub fn calc(msgs: &[WsMessage], since: f64) -> Self {
+        msgs.iter()
+            .filter(|m| trade_time(m).is_some_and(|t| t >= since))
+            .fold(Self::default(), |mut p, m| {
+                let size = m.last_size.parse::<f64>().unwrap_or(0.0);
+                match m.side.as_str() {
+                    "buy" => p.buy_volume += size,
+                    "sell" => p.sell_volume += size,
+                    _ => {}
+                }
+                p
+            })
+    }
+
+    /// Share of the buy volume from 0 to 1, 0.5 when nothing got traded
+    pub fn buy_ratio(&self) -> f64 {
+        let total = self.buy_volume + self.sell_volume;
+        if total <= 0.0 {
+            return 0.5;
+        }
+        self.buy_volume / total
+    }
+
+    /// The color of the side that currently has the upper hand
+    pub fn color(&self, buy: Color, sell: Color) -> Color {
+        if self.buy_ratio() >= 0.5 { buy } else { sell }
+    }
+
+    /// Gauge that fills up with the buy share, the rest is the sell share
+    pub fn gauge(&self, window_secs: u64, buy: Color, sell: Color) -> LineGauge<'static> {
+        let ratio = self.buy_ratio();
+        LineGauge::default()
+            .ratio(ratio)
+            .label(format!(
+                "B {:>3.0}% S {:>3.0}% {window_secs}s",
+                ratio * 100.0,
+                (1.0 - ratio) * 100.0
+            ))
+            .line_set(symbols::line::THICK)
+            .filled_style(Style::new().fg(buy))
+            .unfilled_style(Style::new().fg(sell))
+    }
+}
+
+/// Time of the trade in unix ms
+fn trade_time(m: &WsMessage) -> Option<f64> {
+    m.time
+        .parse::<DateTime<Utc>>()
+        .ok()
+        .map(|t| t.timestamp_millis() as f64)
+}
+
+/// `(time, price)` points of single trades
+pub type Prints = Vec<(f64, f64)>;
+
+/// Splits the trades into the points of buys and sells, so they can be drawn as separate markers
+pub fn prints(msgs: &[WsMessage], since: f64) -> (Prints, Prints) {
+    let mut buys = vec![];
+    let mut sells = vec![];
+
+    for m in msgs {
+        let Some(time) = trade_time(m).filter(|t| *t >= since) else {
+            continue;
+        };
+        let Ok(price) = m.price.parse::<f64>() else {
+            continue;
+        };
+
+        match m.side.as_str() {
+            "buy" => buys.push((time, price)),
+            "sell" => sells.push((time, price)),
+            _ => {}
+        }
+    }
+
+    (buys, sells)
+}
+
+/// Renders the pressure gauge in the first row and the body below it
+pub struct WithPressure<W> {
+    gauge: LineGauge<'static>,
+    body: W,
+}
+
+impl<W> WithPressure<W> {
+    pub fn new(gauge: LineGauge<'static>, body: W) -> Self {
+        Self { gauge, body }
+    }
+}
+
+impl<W: Widget> Widget for WithPressure<W> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let [header, body] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(area);
+        self.gauge.render(header, buf);
+        self.body.render(body, buf);
+    }
+}
@@ -0,0 +1,314 @@
+//! Product discovery
+//!
+//! The list of tradable products comes from the REST api of the exchange and gets cached in the
+//! data directory, so startup doesnt hit the api every time. It is used to validate `--watching`
+//! and for the product picker.
+
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{Context, bail};
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::{Constraint, Flex, Layout, Rect},
+    style::{Style, Stylize},
+    widgets::{Clear, List, ListItem, ListState},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{theme::Theme, utils::data_dir};
+
+/// How long the cached product list is used before it gets fetched again
+const CACHE_TTL_SECS: i64 = 24 * 60 * 60;
+const FETCH_TIMEOUT: Duration = Duration::from_secs(10);
+/// How many close matches get suggested for an unknown product
+const MAX_SUGGESTIONS: usize = 3;
+/// Products further away than this arent suggested
+const MAX_DISTANCE: usize = 2;
+/// How many results the picker shows at most
+const MAX_RESULTS: usize = 50;
+
+fn default_status() -> String {
+    "online".to_string()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProductInfo {
+    /// e.g. `BTC-USD`
+    pub id: String,
+    pub base_currency: String,
+    pub quote_currency: String,
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(default = "default_status")]
+    pub status: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Cache {
+    /// Unix seconds
+    fetched_at: i64,
+    products: Vec<ProductInfo>,
+}
+
+/// Every product of the exchange
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    pub products: Vec<ProductInfo>,
+}
+
+impl Catalog {
+    fn cache_path() -> PathBuf {
+        data_dir().join("products.json")
+    }
+
+    fn read_cache() -> Option<Cache> {
+        let raw = std::fs::read_to_string(Self::cache_path()).ok()?;
+        serde_json::from_str(&raw).ok()
+    }
+
+    async fn fetch(api_url: &str) -> anyhow::Result<Vec<ProductInfo>> {
+        let url = format!("{}/products", api_url.trim_end_matches('/'));
+        let res = reqwest::Client::new()
+            .get(&url)
+            // the exchange rejects requests without a user agent
+            .header(reqwest::header::USER_AGENT, "stonks")
+            .timeout(FETCH_TIMEOUT)
+            .send()
+            .await
+            .with_context(|| format!("Failed to fetch {url}"))?;
+        if !res.status().is_success() {
+            bail!("{url} responded with {}", res.status());
+        }
+
+        let mut products: Vec<ProductInfo> = serde_json::from_str(&res.text().await?)
+            .with_context(|| format!("Unexpected product list from {url}"))?;
+        products.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(products)
+    }
+
+    /// Loads the product list from the cache, or from the api if the cache is too old. A stale
+    /// cache is still better than nothing when the api cant be reached.
+    pub async fn load(api_url: &str) -> anyhow::Result<Self> {
+        let cache = Self::read_cache();
+        let now = chrono::Utc::now().timestamp();
+        if let Some(c) = &cache
+            && now - c.fetched_at < CACHE_TTL_SECS
+        {
+            return Ok(Self {
+                products: c.products.clone(),
+            });
+        }
+
+        match Self::fetch(api_url).await {
+            Ok(products) => {
+                let fresh = Cache {
+                    fetched_at: now,
+                    products,
+                };
+                // not being able to cache only makes the next start slower
+                let _ = std::fs::create_dir_all(data_dir()).and_then(|_| {
+                    std::fs::write(Self::cache_path(), serde_json::to_string(&fresh)?)
+                });
+                Ok(Self {
+                    products: fresh.products,
+                })
+            }
+            Err(e) => match cache {
+                Some(c) => Ok(Self {
+                    products: c.products,
+                }),
+                None => Err(e),
+            },
+        }
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.products.iter().any(|p| p.id == id)
+    }
+
+    /// Whether the product can be subscribed, everything can if the list couldnt be loaded
+    pub fn accepts(&self, id: &str) -> bool {
+        self.products.is_empty() || self.contains(id)
+    }
+
+    /// The closest product ids, for typos like `BTC-USDD`
+    pub fn suggestions(&self, id: &str) -> Vec<String> {
+        let mut close = self
+            .products
+            .iter()
+            .map(|p| (levenshtein(id, &p.id), &p.id))
+            .filter(|(d, _)| *d <= MAX_DISTANCE)
+            .collect::<Vec<(usize, &String)>>();
+        close.sort();
+        close
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, id)| id.clone())
+            .collect()
+    }
+
+    /// Uppercases the products and checks that the exchange knows them. An empty catalog means the
+    /// list couldnt be loaded, then everything is let through.
+    pub fn validate(&self, watching: &[String]) -> anyhow::Result<Vec<String>> {
+        let (known, errors) = self.filter(watching);
+        if !errors.is_empty() {
+            bail!("{}", errors.join("\n"));
+        }
+        Ok(known)
+    }
+
+    /// Like [`Catalog::validate`], but drops the unknown products instead of failing. Returns the
+    /// known ones and an error with suggestions for every unknown one.
+    pub fn filter(&self, products: &[String]) -> (Vec<String>, Vec<String>) {
+        let (known, unknown): (Vec<String>, Vec<String>) = products
+            .iter()
+            .map(|p| p.trim().to_uppercase())
+            .partition(|p| self.accepts(p));
+        let errors = unknown
+            .iter()
+            .map(|p| match self.suggestions(p).as_slice() {
+                [] => format!("Unknown product '{p}'"),
+                s => format!("Unknown product '{p}', did you mean {}?", s.join(", ")),
+            })
+            .collect();
+        (known, errors)
+    }
+
+    /// The products matching the query best, the query is matched against the id and the name
+    pub fn search(&self, query: &str) -> Vec<&ProductInfo> {
+        let mut hits = self
+            .products
+            .iter()
+            .filter(|p| p.status == "online")
+            .filter_map(|p| {
+                let score = fuzzy_score(query, &p.id)
+                    .max(fuzzy_score(query, &p.display_name).map(|s| s / 2))?;
+                Some((score, p))
+            })
+            .collect::<Vec<(i64, &ProductInfo)>>();
+        hits.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.id.cmp(&b.1.id)));
+        hits.into_iter().take(MAX_RESULTS).map(|(_, p)| p).collect()
+    }
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<char>>();
+    let mut prev = (0..=b.len()).collect::<Vec<usize>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut cur = vec![i + 1; b.len() + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let cost = (ca != *cb) as usize;
+            cur[j + 1] = (prev[j] + cost).min(prev[j + 1] + 1).min(cur[j] + 1);
+        }
+        prev = cur;
+    }
+    prev[b.len()]
+}
+
+/// Scores how well `query` matches `candidate`, `None` if the query isnt a subsequence of it.
+/// Consecutive characters and matches at the start of a word score higher.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query = query.to_lowercase();
+    let candidate = candidate.to_lowercase().chars().collect::<Vec<char>>();
+    let mut score = 0;
+    let mut pos = 0;
+    let mut last_match: Option<usize> = None;
+
+    for q in query.chars().filter(|c| !c.is_whitespace()) {
+        let idx = pos + candidate[pos..].iter().position(|c| *c == q)?;
+        score += 1;
+        if last_match.is_some_and(|l| l + 1 == idx) {
+            score += 5;
+        }
+        if idx == 0 || !candidate[idx - 1].is_alphanumeric() {
+            score += 3;
+        }
+        last_match = Some(idx);
+        pos = idx + 1;
+    }
+    // shorter candidates are closer to what got typed
+    Some(score * 10 - candidate.len() as i64)
+}
+
+/// The fuzzy search popup for adding products
+#[derive(Debug, Clone, Default)]
+pub struct Picker {
+    pub query: String,
+    selected: usize,
+}
+
+impl Picker {
+    /// Typing into the search, moving the selection and submitting are keys of command mode
+    pub fn handle_key(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.selected = 0;
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.selected = 0;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn select(&mut self, by: isize, catalog: &Catalog) {
+        let len = catalog.search(&self.query).len();
+        self.selected =
+            (self.selected as isize + by).clamp(0, len.saturating_sub(1) as isize) as usize;
+    }
+
+    /// The id of the selected product
+    pub fn submit(&self, catalog: &Catalog) -> Option<String> {
+        catalog
+            .search(&self.query)
+            .get(self.selected)
+            .map(|p| p.id.clone())
+    }
+
+    pub fn render(
+        &self,
+        frame: &mut Frame,
+        area: Rect,
+        catalog: &Catalog,
+        watching: &[String],
+        theme: &Theme,
+    ) {
+        let [area] = Layout::horizontal([Constraint::Percentage(50)])
+            .flex(Flex::Center)
+            .areas(area);
+        let [area] = Layout::vertical([Constraint::Percentage(70)])
+            .flex(Flex::Center)
+            .areas(area);
+
+        let hits = catalog.search(&self.query);
+        let items = hits.iter().map(|p| {
+            let item = ListItem::new(format!("{:<14} {}", p.id, p.display_name));
+            match watching.contains(&p.id) {
+                true => item.fg(theme.muted),
+                false => item.fg(theme.label),
+            }
+        });
+        let title = match catalog.products.is_empty() {
+            true => "Add product - no product list".to_string(),
+            false => format!("Add product - {}█", self.query),
+        };
+
+        let mut state = ListState::default().with_selected(Some(self.selected));
+        frame.render_widget(Clear, area);
+        frame.render_widget(theme.background_block(), area);
+        frame.render_stateful_widget(
+            theme
+                .panel(
+                    List::new(items)
+                        .highlight_style(Style::new().reversed())
+                        .highlight_symbol("> "),
+                )
+                .title(title),
+            area,
+            &mut state,
+        );
+    }
+}
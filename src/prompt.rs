@@ -0,0 +1,67 @@
+//! Single line text input at the bottom of the screen
+
+use ratatui::{
+    Frame,
+    crossterm::event::{KeyCode, KeyEvent},
+    layout::Rect,
+    style::Stylize,
+    text::Line,
+};
+
+use crate::theme::Theme;
+
+/// What the entered text is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptTarget {
+    /// Create a new alert rule
+    NewAlert,
+    /// Replace the alert rule at the index
+    EditAlert(usize),
+    /// Run a command, see [`crate::commands`]
+    Command,
+}
+
+impl PromptTarget {
+    /// What is shown in front of the text
+    fn label(&self) -> &'static str {
+        match self {
+            PromptTarget::NewAlert => "new alert: ",
+            PromptTarget::EditAlert(_) => "edit alert: ",
+            PromptTarget::Command => ":",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Prompt {
+    pub target: PromptTarget,
+    pub text: String,
+}
+
+impl Prompt {
+    pub fn new(target: PromptTarget, text: String) -> Self {
+        Self { target, text }
+    }
+
+    /// Typing, submitting and cancelling are keys of command mode
+    pub fn handle_key(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Backspace => {
+                self.text.pop();
+            }
+            KeyCode::Char(c) => self.text.push(c),
+            _ => {}
+        }
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect, theme: &Theme) {
+        frame.render_widget(
+            Line::from(vec![
+                self.target.label().fg(theme.warning),
+                self.text.clone().fg(theme.label),
+                "█".fg(theme.label),
+            ]),
+            area,
+        );
+    }
+}
@@ -1,17 +1,24 @@
-use std::{collections::HashMap, sync::Arc};
+use std::{
+    collections::HashMap,
+    sync::{
+        Arc,
+        atomic::{AtomicU64, Ordering},
+    },
+};
 
-use clap::Parser;
+use chrono::{DateTime, Utc};
 use futures::{SinkExt, StreamExt};
 use parking_lot::Mutex;
 use ringbuffer::{AllocRingBuffer, RingBuffer};
 use serde::{Deserialize, Serialize};
 use serde_json::json;
+use tokio::sync::mpsc;
 use tokio_tungstenite::{
     connect_async,
     tungstenite::{Message, Utf8Bytes, client::IntoClientRequest},
 };
 
-use crate::{opts::CliOpts, utils::FEED_WS_URL};
+use crate::utils::FEED_WS_URL;
 
 // TODO: alloc 5k for each coin
 lazy_static::lazy_static! {
@@ -19,6 +26,14 @@ lazy_static::lazy_static! {
                 Arc::new(Mutex::new(HashMap::new()));
 }
 
+/// Goes up with every stored message, so the ui knows when there is something new to draw
+static MESSAGE_COUNT: AtomicU64 = AtomicU64::new(0);
+
+/// How many messages were stored since the start
+pub fn message_count() -> u64 {
+    MESSAGE_COUNT.load(Ordering::Relaxed)
+}
+
 crate::pub_fields! {
     #[derive(Debug, Clone, Deserialize, Serialize, Default)]
     struct WsMessage {
@@ -59,54 +74,119 @@ crate::pub_fields! {
     }
 }
 
+/// Returns the buffered `(timestamp_ms, price)` points of a product, oldest first
+pub fn price_history(product: &str) -> Vec<(f64, f64)> {
+    match ws_messages.lock().get(product) {
+        Some(v) => v
+            .iter()
+            .filter_map(|m| {
+                let time = m.time.parse::<DateTime<Utc>>().ok()?;
+                Some((time.timestamp_millis() as f64, m.price.parse::<f64>().ok()?))
+            })
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// The first price within `window_ms` before the newest message and the newest price of a
+/// product. Only looks at a few messages instead of parsing all of them like [`price_history`].
+pub fn window_prices(product: &str, window_ms: f64) -> Option<(f64, f64)> {
+    let msgs = ws_messages.lock();
+    let buf = msgs.get(product)?;
+    let point = |m: &WsMessage| {
+        let time = m.time.parse::<DateTime<Utc>>().ok()?;
+        Some((time.timestamp_millis() as f64, m.price.parse::<f64>().ok()?))
+    };
+    let (last_time, last) = point(buf.back()?)?;
+    let since = last_time - window_ms;
+
+    // the messages are in time order, so the start of the window can be searched for
+    let before = |i: usize| buf.get(i).and_then(point).is_none_or(|(t, _)| t < since);
+    let (mut lo, mut hi) = (0, buf.len() - 1);
+    while lo < hi {
+        let mid = (lo + hi) / 2;
+        match before(mid) {
+            true => lo = mid + 1,
+            false => hi = mid,
+        }
+    }
+    let (_, first) = buf.get(lo).and_then(point)?;
+    Some((first, last))
+}
+
+/// Returns the newest message of a product
+pub fn last_message(product: &str) -> Option<WsMessage> {
+    ws_messages.lock().get(product)?.back().cloned()
+}
+
+/// Changes to the subscribed products while the feed is running
+#[derive(Debug, Clone)]
+pub enum FeedCommand {
+    Subscribe(Vec<String>),
+    Unsubscribe(Vec<String>),
+}
+
+fn subscription(kind: &str, products: &[String]) -> Message {
+    Message::text(
+        json!({
+              "type": kind,
+              "channels": ["ticker"],
+              "product_ids": products
+        })
+        .to_string(),
+    )
+}
+
 pub struct BaseSocket {}
 
 impl BaseSocket {
-    pub async fn connect(products: Vec<String>) -> anyhow::Result<()> {
+    pub async fn connect(
+        products: Vec<String>,
+        capacity: usize,
+        mut commands: mpsc::UnboundedReceiver<FeedCommand>,
+    ) -> anyhow::Result<()> {
         let req = FEED_WS_URL.into_client_request().unwrap();
 
         let (stream, _res) = connect_async(req).await.unwrap();
         let (mut tx, mut rx) = stream.split();
 
-        let msg = Message::text(
-            json!({
-                  "type": "subscribe",
-                  "channels": ["ticker"],
-                  "product_ids": products
-            })
-            .to_string(),
-        );
-
-        tx.send(msg).await.unwrap();
-
-        while let Some(msg) = rx.next().await {
-            match msg?.clone() {
-                Message::Text(m) => {
-                    let _ = Self::handle_message(m).await;
+        tx.send(subscription("subscribe", &products)).await.unwrap();
+
+        loop {
+            tokio::select! {
+                msg = rx.next() => {
+                    let Some(msg) = msg else { break };
+                    match msg?.clone() {
+                        Message::Text(m) => {
+                            let _ = Self::handle_message(m, capacity).await;
+                        }
+                        Message::Ping(m) => tx.send(Message::Pong(m)).await?,
+                        _ => {}
+                    }
                 }
-                Message::Ping(m) => tx.send(Message::Pong(m)).await?,
-                _ => {}
+                Some(cmd) = commands.recv() => match cmd {
+                    FeedCommand::Subscribe(p) => tx.send(subscription("subscribe", &p)).await?,
+                    FeedCommand::Unsubscribe(p) => {
+                        tx.send(subscription("unsubscribe", &p)).await?
+                    }
+                },
             }
         }
 
         Ok(())
     }
 
-    async fn handle_message(m: Utf8Bytes) -> anyhow::Result<()> {
+    /// `capacity` is how many messages are kept per product
+    async fn handle_message(m: Utf8Bytes, capacity: usize) -> anyhow::Result<()> {
         let msg = m.as_str();
         let p_msg: WsMessage = serde_json::from_str(msg)?;
 
         let mut l = ws_messages.lock();
 
-        if !l.contains_key(&p_msg.product_id) {
-            l.insert(
-                p_msg.product_id,
-                AllocRingBuffer::new(CliOpts::parse().watching.len() * 10000),
-            );
-            return Ok(());
-        }
-
-        l.get_mut(&p_msg.product_id).unwrap().enqueue(p_msg);
+        l.entry(p_msg.product_id.clone())
+            .or_insert_with(|| AllocRingBuffer::new(capacity))
+            .enqueue(p_msg);
+        MESSAGE_COUNT.fetch_add(1, Ordering::Relaxed);
 
         Ok(())
     }
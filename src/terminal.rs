@@ -0,0 +1,298 @@
+//! Terminal capabilities
+//!
+//! Not every terminal shows 24 bit colors and box drawing characters, serial consoles, older ssh
+//! clients and tmux without truecolor dont. The colors are detected from the environment:
+//! `NO_COLOR` turns them off, `COLORTERM=truecolor` or `24bit` keeps all of them, a `TERM` with
+//! `256color` gets the 256 color palette and any other `TERM` the 16 ansi colors. Everything that
+//! gets drawn is brought down to the nearest color the terminal has, so gradients and themes dont
+//! need to know about it.
+//!
+//! ```toml
+//! [display]
+//! # auto, truecolor, 256, 16 or none
+//! colors = "auto"
+//! # rounded, plain, thick, double or ascii, ascii also turns the chart axes into ascii
+//! borders = "rounded"
+//! # how the chart lines are drawn, braille, half-block, dot, block or bar
+//! marker = "braille"
+//! ```
+
+use ratatui::{buffer::Buffer, style::Color, symbols::Marker};
+use serde::Deserialize;
+
+use crate::gradient_widget::{BorderSymbols, to_rgb};
+
+/// The levels of the 6x6x6 color cube of the 256 color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+/// The colors the 16 color palette is matched against
+const ANSI: [Color; 16] = [
+    Color::Black,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::Gray,
+    Color::DarkGray,
+    Color::LightRed,
+    Color::LightGreen,
+    Color::LightYellow,
+    Color::LightBlue,
+    Color::LightMagenta,
+    Color::LightCyan,
+    Color::White,
+];
+
+/// How many colors the terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorSupport {
+    /// Detected from the environment
+    #[default]
+    Auto,
+    Truecolor,
+    #[serde(rename = "256")]
+    #[value(name = "256")]
+    Palette256,
+    #[serde(rename = "16")]
+    #[value(name = "16")]
+    Ansi16,
+    /// Only the colors of the terminal itself, like with `NO_COLOR`
+    None,
+}
+
+impl ColorSupport {
+    /// Replaces `Auto` with what the environment says
+    pub fn resolve(self) -> Self {
+        match self {
+            ColorSupport::Auto => Self::from_env(
+                std::env::var("NO_COLOR").ok(),
+                std::env::var("COLORTERM").ok(),
+                std::env::var("TERM").ok(),
+            ),
+            s => s,
+        }
+    }
+
+    fn from_env(no_color: Option<String>, colorterm: Option<String>, term: Option<String>) -> Self {
+        if no_color.is_some_and(|v| !v.is_empty()) {
+            return ColorSupport::None;
+        }
+        if colorterm.is_some_and(|c| matches!(c.to_lowercase().as_str(), "truecolor" | "24bit")) {
+            return ColorSupport::Truecolor;
+        }
+        match term.map(|t| t.to_lowercase()) {
+            // without a TERM nothing can be told, like on windows, so everything stays
+            None => ColorSupport::Truecolor,
+            Some(t) if t.contains("direct") => ColorSupport::Truecolor,
+            Some(t) if t.contains("256color") => ColorSupport::Palette256,
+            Some(t) if t == "dumb" => ColorSupport::None,
+            Some(_) => ColorSupport::Ansi16,
+        }
+    }
+
+    /// The nearest color the terminal can show
+    pub fn map(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorSupport::Auto | ColorSupport::Truecolor, c) => c,
+            (ColorSupport::None, _) => Color::Reset,
+            (ColorSupport::Palette256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_256(r, g, b)),
+            (ColorSupport::Ansi16, c @ (Color::Rgb(..) | Color::Indexed(_))) => match to_rgb(c) {
+                Some(rgb) => nearest_ansi(rgb),
+                None => c,
+            },
+            (_, c) => c,
+        }
+    }
+}
+
+/// The characters of the borders
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum BorderStyle {
+    #[default]
+    Rounded,
+    Plain,
+    Thick,
+    Double,
+    Ascii,
+}
+
+impl BorderStyle {
+    pub fn symbols(self) -> BorderSymbols {
+        match self {
+            BorderStyle::Rounded => BorderSymbols::ROUNDED,
+            BorderStyle::Plain => BorderSymbols::PLAIN,
+            BorderStyle::Thick => BorderSymbols::THICK,
+            BorderStyle::Double => BorderSymbols::DOUBLE,
+            BorderStyle::Ascii => BorderSymbols::ASCII,
+        }
+    }
+}
+
+/// How the lines of the charts are drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ChartMarker {
+    #[default]
+    Braille,
+    HalfBlock,
+    Dot,
+    Block,
+    Bar,
+}
+
+impl From<ChartMarker> for Marker {
+    fn from(m: ChartMarker) -> Self {
+        match m {
+            ChartMarker::Braille => Marker::Braille,
+            ChartMarker::HalfBlock => Marker::HalfBlock,
+            ChartMarker::Dot => Marker::Dot,
+            ChartMarker::Block => Marker::Block,
+            ChartMarker::Bar => Marker::Bar,
+        }
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> i32 {
+    let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+    d(r1, r2) + d(g1, g2) + d(b1, b2)
+}
+
+/// The closest color of the 256 color palette, either from the color cube or the gray ramp
+fn nearest_256(r: u8, g: u8, b: u8) -> u8 {
+    let level = |v: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|i| (CUBE_LEVELS[*i] as i32 - v as i32).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = (CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]);
+
+    let avg = (r as i32 + g as i32 + b as i32) / 3;
+    let gray_idx = ((avg - 8).max(0) / 10).min(23);
+    let gray = (8 + gray_idx * 10) as u8;
+
+    match distance((r, g, b), (gray, gray, gray)) < distance((r, g, b), cube) {
+        true => 232 + gray_idx as u8,
+        false => (16 + 36 * ri + 6 * gi + bi) as u8,
+    }
+}
+
+fn nearest_ansi(rgb: (u8, u8, u8)) -> Color {
+    ANSI.into_iter()
+        .min_by_key(|c| to_rgb(*c).map_or(i32::MAX, |c| distance(c, rgb)))
+        .unwrap_or(Color::Reset)
+}
+
+/// The ascii version of line drawing and block characters
+fn ascii(c: char) -> Option<char> {
+    match c {
+        '─' | '━' | '═' | '┄' | '┅' | '╌' | '╍' => Some('-'),
+        '│' | '┃' | '║' | '┆' | '┇' | '╎' | '╏' => Some('|'),
+        '\u{2500}'..='\u{257f}' => Some('+'),
+        '•' => Some('*'),
+        '\u{2580}'..='\u{259f}' => Some('#'),
+        _ => None,
+    }
+}
+
+/// Brings the drawn frame down to what the terminal can show, after everything else got drawn
+pub fn degrade(buf: &mut Buffer, colors: ColorSupport, borders: BorderStyle) {
+    let ascii_only = borders == BorderStyle::Ascii;
+    if matches!(colors, ColorSupport::Auto | ColorSupport::Truecolor) && !ascii_only {
+        return;
+    }
+
+    for cell in buf.content.iter_mut() {
+        cell.fg = colors.map(cell.fg);
+        cell.bg = colors.map(cell.bg);
+        if ascii_only && let Some(c) = cell.symbol().chars().next().and_then(ascii) {
+            cell.set_char(c);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{layout::Rect, style::Style};
+
+    use super::*;
+
+    #[test]
+    fn detects_colors_from_env() {
+        let s = |v: &str| (!v.is_empty()).then(|| v.to_string());
+        for (no_color, colorterm, term, expected) in [
+            ("1", "truecolor", "xterm-256color", ColorSupport::None),
+            // an empty NO_COLOR doesnt count
+            ("", "truecolor", "xterm", ColorSupport::Truecolor),
+            ("", "24bit", "dumb", ColorSupport::Truecolor),
+            ("", "TrueColor", "", ColorSupport::Truecolor),
+            ("", "", "xterm-256color", ColorSupport::Palette256),
+            ("", "", "screen-256color", ColorSupport::Palette256),
+            ("", "yes", "tmux-256color", ColorSupport::Palette256),
+            ("", "", "xterm-direct", ColorSupport::Truecolor),
+            ("", "", "xterm", ColorSupport::Ansi16),
+            ("", "", "linux", ColorSupport::Ansi16),
+            ("", "", "dumb", ColorSupport::None),
+            ("", "", "", ColorSupport::Truecolor),
+        ] {
+            assert_eq!(
+                ColorSupport::from_env(s(no_color), s(colorterm), s(term)),
+                expected,
+                "NO_COLOR={no_color} COLORTERM={colorterm} TERM={term}"
+            );
+        }
+    }
+
+    #[test]
+    fn finds_the_nearest_256_color() {
+        for (rgb, expected) in [
+            ((0, 0, 0), 16),
+            ((255, 255, 255), 231),
+            ((255, 0, 0), 196),
+            ((0, 255, 0), 46),
+            ((95, 135, 175), 67),
+            ((100, 140, 170), 67),
+            // grays go to the gray ramp where it is closer than the cube
+            ((8, 8, 8), 232),
+            ((128, 128, 128), 244),
+            ((238, 238, 238), 255),
+        ] {
+            assert_eq!(nearest_256(rgb.0, rgb.1, rgb.2), expected, "{rgb:?}");
+        }
+    }
+
+    fn drawn() -> Buffer {
+        let mut buf = Buffer::empty(Rect::new(0, 0, 4, 1));
+        let style = Style::default()
+            .fg(Color::Rgb(247, 147, 26))
+            .bg(Color::Rgb(10, 20, 30));
+        buf.set_string(0, 0, "╭─•▀", style);
+        buf
+    }
+
+    #[test]
+    fn leaves_truecolor_alone() {
+        for colors in [ColorSupport::Truecolor, ColorSupport::Auto] {
+            let mut buf = drawn();
+            degrade(&mut buf, colors, BorderStyle::Rounded);
+            assert_eq!(buf, drawn());
+        }
+    }
+
+    #[test]
+    fn degrades_colors_and_borders() {
+        let mut buf = drawn();
+        degrade(&mut buf, ColorSupport::Palette256, BorderStyle::Rounded);
+        assert_eq!(buf[(0, 0)].fg, Color::Indexed(nearest_256(247, 147, 26)));
+        assert_eq!(buf[(0, 0)].symbol(), "╭");
+
+        let mut buf = drawn();
+        degrade(&mut buf, ColorSupport::None, BorderStyle::Ascii);
+        let symbols = buf.content.iter().map(|c| c.symbol()).collect::<String>();
+        assert_eq!(symbols, "+-*#");
+        assert!(buf.content.iter().all(|c| c.fg == Color::Reset));
+    }
+}
@@ -0,0 +1,261 @@
+//! Themes
+//!
+//! Every color of the ui that isnt part of the data itself. Themes are toml files, `dark`, `light`
+//! and `high-contrast` are bundled and own ones go into `themes/<name>.toml` in the config
+//! directory. A theme only needs the keys it changes, everything else comes from the theme it
+//! `extends`, or from the dark colors without any coins.
+//!
+//! ```toml
+//! extends = "dark"
+//!
+//! # price went up/ down, buys and sells
+//! up = "#00ff64"
+//! down = "#ff0064"
+//! # chart axes and the text on them
+//! axis = "white"
+//! label = "white"
+//! # less important text, like finished alerts
+//! muted = "darkgray"
+//! # "reset" keeps the background of the terminal
+//! background = "reset"
+//! # borders of everything that isnt a coin chart
+//! border = "white"
+//! info = "#00ff64"
+//! warning = "yellow"
+//! error = "#ff0064"
+//!
+//! # coins without an entry get a gradient generated from their name
+//! auto_saturation = 0.75
+//! auto_lightness = 0.6
+//!
+//! # like the coins in config.toml, colors, hex strings or a preset
+//! [coins]
+//! BTC = "#f7931a"
+//! SOL = ["#9a45fe", "#5798cb", "#15f096", "#5798cb"]
+//! DOGE = { preset = "sunset", blend = "oklab" }
+//! ```
+
+use std::collections::HashMap;
+
+use anyhow::bail;
+use ratatui::{
+    style::{Color, Style},
+    symbols::Marker,
+    widgets::Block,
+};
+use serde::Deserialize;
+
+use crate::{
+    config::{ConfigColor, GradientColors},
+    gradient_widget::{BorderSymbols, GradientConfig, GradientWrapper},
+    utils::load_named_toml,
+};
+
+pub const DEFAULT_THEME: &str = "dark";
+const BUNDLED: [(&str, &str); 3] = [
+    ("dark", include_str!("../themes/dark.toml")),
+    ("light", include_str!("../themes/light.toml")),
+    (
+        "high-contrast",
+        include_str!("../themes/high-contrast.toml"),
+    ),
+];
+/// How deep `extends` may chain, so two themes extending each other dont loop forever
+const MAX_EXTENDS: usize = 8;
+
+#[derive(Debug, Clone)]
+pub struct Theme {
+    pub up: Color,
+    pub down: Color,
+    pub axis: Color,
+    pub label: Color,
+    pub muted: Color,
+    pub background: Color,
+    pub border: Color,
+    pub info: Color,
+    pub warning: Color,
+    pub error: Color,
+    pub auto_saturation: f32,
+    pub auto_lightness: f32,
+    /// Border gradients per base asset
+    pub coins: HashMap<String, GradientConfig>,
+    /// From the display config, theme files dont set these
+    pub borders: BorderSymbols,
+    pub marker: Marker,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            up: Color::Rgb(0, 255, 100),
+            down: Color::Rgb(255, 0, 100),
+            axis: Color::White,
+            label: Color::White,
+            muted: Color::DarkGray,
+            background: Color::Reset,
+            border: Color::White,
+            info: Color::Rgb(0, 255, 100),
+            warning: Color::Yellow,
+            error: Color::Rgb(255, 0, 100),
+            auto_saturation: 0.75,
+            auto_lightness: 0.6,
+            coins: HashMap::new(),
+            borders: BorderSymbols::default(),
+            marker: Marker::Braille,
+        }
+    }
+}
+
+/// A theme like it is written in the file, everything that is missing comes from the parent
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeFile {
+    pub extends: Option<String>,
+    pub up: Option<ConfigColor>,
+    pub down: Option<ConfigColor>,
+    pub axis: Option<ConfigColor>,
+    pub label: Option<ConfigColor>,
+    pub muted: Option<ConfigColor>,
+    pub background: Option<ConfigColor>,
+    pub border: Option<ConfigColor>,
+    pub info: Option<ConfigColor>,
+    pub warning: Option<ConfigColor>,
+    pub error: Option<ConfigColor>,
+    pub auto_saturation: Option<f32>,
+    pub auto_lightness: Option<f32>,
+    #[serde(default)]
+    pub coins: HashMap<String, GradientColors>,
+}
+
+impl ThemeFile {
+    fn parse(raw: &str) -> anyhow::Result<Self> {
+        let file: Self = toml::from_str(raw)?;
+        for (key, v) in [
+            ("auto_saturation", file.auto_saturation),
+            ("auto_lightness", file.auto_lightness),
+        ] {
+            if v.is_some_and(|v| !(0.0..=1.0).contains(&v)) {
+                bail!("{key} has to be between 0 and 1");
+            }
+        }
+        Ok(file)
+    }
+
+    /// Puts everything that is set in here on top of `theme`
+    fn apply(&self, theme: &mut Theme) {
+        let colors = [
+            (&mut theme.up, self.up),
+            (&mut theme.down, self.down),
+            (&mut theme.axis, self.axis),
+            (&mut theme.label, self.label),
+            (&mut theme.muted, self.muted),
+            (&mut theme.background, self.background),
+            (&mut theme.border, self.border),
+            (&mut theme.info, self.info),
+            (&mut theme.warning, self.warning),
+            (&mut theme.error, self.error),
+        ];
+        for (field, c) in colors {
+            if let Some(c) = c {
+                *field = c.0;
+            }
+        }
+        if let Some(s) = self.auto_saturation {
+            theme.auto_saturation = s;
+        }
+        if let Some(l) = self.auto_lightness {
+            theme.auto_lightness = l;
+        }
+        theme.coins.extend(
+            self.coins
+                .iter()
+                .map(|(coin, c)| (coin.to_uppercase(), c.0.clone())),
+        );
+    }
+}
+
+impl Theme {
+    /// Loads a theme by name or path. Own themes in the config directory win over the bundled
+    /// ones, so `dark.toml` in there replaces the bundled dark theme.
+    pub fn load(name: &str) -> anyhow::Result<Self> {
+        Self::resolve_from(name, &Self::load_file(name, false)?, 0)
+    }
+
+    /// `bundled` skips the own themes, so an own `dark.toml` can extend the bundled one
+    fn load_file(name: &str, bundled: bool) -> anyhow::Result<ThemeFile> {
+        load_named_toml("theme", "themes", name, &BUNDLED, bundled, ThemeFile::parse)
+    }
+
+    /// The theme with everything it extends below it
+    fn resolve_from(name: &str, file: &ThemeFile, depth: usize) -> anyhow::Result<Self> {
+        if depth > MAX_EXTENDS {
+            bail!("Theme '{name}' extends too many themes, is there a loop?");
+        }
+        let mut theme = match &file.extends {
+            Some(parent) => {
+                let parent_file = Self::load_file(parent, parent == name)?;
+                Self::resolve_from(parent, &parent_file, depth + 1)?
+            }
+            None => Self::default(),
+        };
+        file.apply(&mut theme);
+        Ok(theme)
+    }
+
+    /// The border gradient of a base asset like `BTC`, generated from its name if the theme
+    /// doesnt have one, so every coin always gets the same colors
+    pub fn coin_gradient(&self, base: &str) -> GradientConfig {
+        if let Some(g) = self.coins.get(&base.to_uppercase()) {
+            return g.clone();
+        }
+
+        let hash = fnv1a(&base.to_uppercase());
+        let hue = (hash % 360) as f32;
+        let shift = 40.0 + ((hash >> 16) % 60) as f32;
+        let (s, l) = (self.auto_saturation, self.auto_lightness);
+        GradientConfig::new_2(hsl(hue, s, l), hsl(hue + shift, s, l))
+    }
+
+    /// One color of a product like `BTC-USD`, for lines and legends
+    pub fn coin_color(&self, product: &str) -> Color {
+        self.coin_gradient(product.split('-').next().unwrap_or_default())
+            .top_start
+    }
+
+    /// Fills an area with the background, text without its own color gets the label color
+    pub fn background_block(&self) -> Block<'static> {
+        Block::new().style(Style::new().bg(self.background).fg(self.label))
+    }
+
+    /// Wraps the widget in the border that every panel besides the coin charts has
+    pub fn panel<W>(&self, widget: W) -> GradientWrapper<W> {
+        GradientWrapper::new(widget)
+            .gradient_colors(GradientConfig::new_1(self.border))
+            .title_color(self.label)
+            .border_symbols(self.borders)
+    }
+}
+
+/// Hashes the same on every run and every platform, unlike the std hasher
+fn fnv1a(s: &str) -> u32 {
+    s.bytes()
+        .fold(0x811c9dc5, |h, b| (h ^ b as u32).wrapping_mul(0x01000193))
+}
+
+/// `hue` in degrees, saturation and lightness from 0 to 1
+fn hsl(hue: f32, s: f32, l: f32) -> Color {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let h = hue.rem_euclid(360.0) / 60.0;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = l - c / 2.0;
+    let to_u8 = |v: f32| ((v + m) * 255.0).round().clamp(0.0, 255.0) as u8;
+    Color::Rgb(to_u8(r), to_u8(g), to_u8(b))
+}
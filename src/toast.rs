@@ -0,0 +1,82 @@
+//! Toasts
+//!
+//! Short messages that show up in the top right corner of the body for a few seconds, like the
+//! result of reloading the config.
+
+use ratatui::{
+    Frame,
+    layout::Rect,
+    widgets::{Clear, Paragraph, Wrap},
+};
+
+use crate::{gradient_widget::GradientConfig, theme::Theme};
+
+/// How long a toast is shown
+const TOAST_MS: i64 = 5000;
+/// Errors stay a bit longer, they usually need to be read
+const ERROR_TOAST_MS: i64 = 10000;
+
+#[derive(Debug, Clone)]
+pub struct Toast {
+    pub message: String,
+    pub error: bool,
+    /// Unix ms after which the toast is gone
+    pub until: i64,
+}
+
+impl Toast {
+    fn new(message: impl Into<String>, error: bool, ms: i64) -> Self {
+        Self {
+            message: message.into(),
+            error,
+            until: chrono::Local::now().timestamp_millis() + ms,
+        }
+    }
+
+    pub fn info(message: impl Into<String>) -> Self {
+        Self::new(message, false, TOAST_MS)
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Self::new(message, true, ERROR_TOAST_MS)
+    }
+
+    pub fn expired(&self) -> bool {
+        chrono::Local::now().timestamp_millis() >= self.until
+    }
+}
+
+pub fn render_toast(frame: &mut Frame, area: Rect, toast: &Toast, theme: &Theme) {
+    let longest = toast
+        .message
+        .lines()
+        .map(|l| l.chars().count())
+        .max()
+        .unwrap_or(0) as u16;
+    let width = (longest + 4)
+        .clamp(20, (area.width * 2 / 3).max(20))
+        .min(area.width);
+    let inner = width.saturating_sub(2).max(1) as usize;
+    // the wrapped height, every line takes at least one row
+    let lines = toast
+        .message
+        .lines()
+        .map(|l| l.chars().count().div_ceil(inner).max(1))
+        .sum::<usize>() as u16;
+    let height = (lines + 2).min(area.height);
+    let area = Rect::new(area.right() - width, area.y, width, height);
+
+    let (title, color) = match toast.error {
+        true => ("Error", theme.error),
+        false => ("Info", theme.info),
+    };
+    frame.render_widget(Clear, area);
+    frame.render_widget(theme.background_block(), area);
+    frame.render_widget(
+        theme
+            .panel(Paragraph::new(toast.message.as_str()).wrap(Wrap { trim: false }))
+            .title(title)
+            .gradient_colors(GradientConfig::new_1(color)),
+        area,
+    );
+}
@@ -1,4 +1,5 @@
 // This file is more or less depricated
+#![allow(dead_code)]
 
 
 
@@ -9,7 +10,7 @@
 use terminal_size::terminal_size;
 use std::io::{self, Write};
 
-const ESC: &'static str = "\x1b";
+const ESC: &str = "\x1b";
 
 
 pub const TOP_LEFT: char = '╭';